@@ -1,10 +1,11 @@
 use std::io;
-use winres::WindowsResource;
 
 fn main() -> io::Result<()> {
+    // The icon and version resources only matter for the windowed build
+    #[cfg(feature = "gui")]
     if cfg!(target_os = "windows") {
-        WindowsResource::new()
+        winres::WindowsResource::new()
             .compile()?;
     }
     Ok(())
-}
\ No newline at end of file
+}
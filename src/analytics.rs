@@ -0,0 +1,352 @@
+//! Progression over time from the training log: estimated one-rep max and
+//! tonnage per exercise, weekly sets per muscle, and exercises that have
+//! stopped improving.
+
+use crate::library::Library;
+use crate::training_log::{LoggedExercise, LoggedSet, TrainingLog};
+use crate::volume::VolumeConfig;
+use chrono::{Datelike, Duration, NaiveDate};
+use std::collections::BTreeMap;
+
+/// Sets of more reps than this say little about the one-rep max.
+const MAX_E1RM_REPS: u32 = 12;
+
+/// Relative gain in e1RM that counts as a new best when looking for stalls.
+const STALL_MARGIN: f64 = 0.01;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum E1rmFormula {
+    #[default]
+    Epley,
+    Brzycki,
+}
+
+impl E1rmFormula {
+    pub const ALL: [E1rmFormula; 2] = [E1rmFormula::Epley, E1rmFormula::Brzycki];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            E1rmFormula::Epley => "Epley",
+            E1rmFormula::Brzycki => "Brzycki",
+        }
+    }
+
+    /// Estimated one-rep max from a set of `reps` with `load`. `None` for
+    /// sets without reps or load, or with more than 12 reps.
+    pub fn estimate(self, load: f64, reps: u32) -> Option<f64> {
+        if reps == 0 || reps > MAX_E1RM_REPS || load <= 0.0 {
+            return None;
+        }
+        if reps == 1 {
+            return Some(load);
+        }
+        let reps = reps as f64;
+        Some(match self {
+            E1rmFormula::Epley => load * (1.0 + reps / 30.0),
+            E1rmFormula::Brzycki => load * 36.0 / (37.0 - reps),
+        })
+    }
+}
+
+impl std::str::FromStr for E1rmFormula {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        E1rmFormula::ALL
+            .into_iter()
+            .find(|f| f.label().eq_ignore_ascii_case(s))
+            .ok_or(())
+    }
+}
+
+/// One day's work on an exercise. Sessions on the same day are combined.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgressPoint {
+    pub date: NaiveDate,
+    /// Best estimated one-rep max of the day's sets, if any set allows one.
+    pub e1rm: Option<f64>,
+    /// Sum of reps times load.
+    pub tonnage: f64,
+    pub sets: usize,
+    /// The set the e1RM comes from.
+    pub top_set: Option<LoggedSet>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExerciseProgress {
+    pub exercise_id: u32,
+    pub points: Vec<ProgressPoint>,
+}
+
+/// No new best e1RM in the most recent sessions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stall {
+    pub exercise_id: u32,
+    /// Best e1RM before the stall, and when it was set.
+    pub best: f64,
+    pub best_date: NaiveDate,
+    /// Sessions since then that did not beat it.
+    pub sessions: usize,
+}
+
+impl ExerciseProgress {
+    /// `None` unless each of the last `sessions` sessions with an e1RM
+    /// failed to beat the best one before them by more than 1%.
+    pub fn stall(&self, sessions: usize) -> Option<Stall> {
+        let estimated: Vec<(NaiveDate, f64)> = self.points.iter().filter_map(|p| Some((p.date, p.e1rm?))).collect();
+        if sessions == 0 || estimated.len() <= sessions {
+            return None;
+        }
+        let (before, recent) = estimated.split_at(estimated.len() - sessions);
+        // The first session to reach the best e1RM, not the last to match it
+        let (best_index, &(best_date, best)) = before
+            .iter()
+            .enumerate()
+            .rev()
+            .max_by(|a, b| a.1.1.total_cmp(&b.1.1))?;
+        let recent_best = recent.iter().map(|(_, e1rm)| *e1rm).fold(f64::MIN, f64::max);
+        (recent_best <= best * (1.0 + STALL_MARGIN)).then(|| Stall {
+            exercise_id: self.exercise_id,
+            best,
+            best_date,
+            sessions: estimated.len() - 1 - best_index,
+        })
+    }
+}
+
+impl TrainingLog {
+    /// Progress of every logged exercise, in order of exercise id.
+    pub fn progress(&self, formula: E1rmFormula) -> Vec<ExerciseProgress> {
+        let mut by_exercise: BTreeMap<u32, BTreeMap<NaiveDate, Vec<&LoggedExercise>>> = BTreeMap::new();
+        for entry in &self.entries {
+            for logged in &entry.session.exercises {
+                by_exercise
+                    .entry(logged.exercise_id)
+                    .or_default()
+                    .entry(entry.session.date)
+                    .or_default()
+                    .push(logged);
+            }
+        }
+
+        by_exercise
+            .into_iter()
+            .map(|(exercise_id, days)| ExerciseProgress {
+                exercise_id,
+                points: days
+                    .into_iter()
+                    .map(|(date, logged)| {
+                        let sets: Vec<LoggedSet> = logged.iter().flat_map(|l| l.sets.iter().copied()).collect();
+                        let top_set = sets
+                            .iter()
+                            .copied()
+                            .filter_map(|s| Some((s, formula.estimate(s.load, s.reps)?)))
+                            .max_by(|a, b| a.1.total_cmp(&b.1));
+                        ProgressPoint {
+                            date,
+                            e1rm: top_set.map(|(_, e1rm)| e1rm),
+                            tonnage: sets.iter().map(|s| s.reps as f64 * s.load).sum(),
+                            sets: sets.len(),
+                            top_set: top_set.map(|(set, _)| set),
+                        }
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Hard sets per muscle for each week (starting Monday) with logged
+    /// sessions, credited like a program's weekly volume. Sets without an
+    /// RIR rating count as hard sets.
+    pub fn weekly_muscle_volume(&self, library: &Library, config: &VolumeConfig) -> BTreeMap<NaiveDate, BTreeMap<String, f64>> {
+        let mut weeks: BTreeMap<NaiveDate, BTreeMap<String, f64>> = BTreeMap::new();
+        for entry in &self.entries {
+            let date = entry.session.date;
+            let week_start = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+            let week = weeks.entry(week_start).or_default();
+            for logged in &entry.session.exercises {
+                let Some(library_entry) = library.find_by_id(logged.exercise_id) else {
+                    continue;
+                };
+                let exercise = &library_entry.exercise;
+                let hard_sets = logged
+                    .sets
+                    .iter()
+                    .filter(|s| s.rir.is_none_or(|rir| rir <= config.hard_set_max_rir))
+                    .count() as f64;
+                for muscle in &exercise.target_muscles.muscles {
+                    let credit = hard_sets * config.credit(&exercise.r#type, muscle.role);
+                    if credit > 0.0 {
+                        *week.entry(muscle.muscle_name.clone()).or_default() += credit;
+                    }
+                }
+            }
+        }
+        weeks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::LibraryEntry;
+    use crate::models::*;
+    use crate::training_log::{LogEntry, LoggedSession};
+    use std::path::PathBuf;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, day).unwrap()
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    fn set(reps: u32, load: f64, rir: Option<u32>) -> LoggedSet {
+        LoggedSet { reps, load, rir }
+    }
+
+    fn session(day: u32, exercise_id: u32, sets: Vec<LoggedSet>) -> LogEntry {
+        LogEntry {
+            path: PathBuf::from(format!("{}.json", day)),
+            session: LoggedSession {
+                exercises: vec![LoggedExercise { exercise_id, sets, notes: String::new() }],
+                ..LoggedSession::new(date(day))
+            },
+        }
+    }
+
+    /// Progress with one point per day, e1RMs as given.
+    fn progress(e1rms: &[Option<f64>]) -> ExerciseProgress {
+        ExerciseProgress {
+            exercise_id: 0,
+            points: e1rms
+                .iter()
+                .enumerate()
+                .map(|(i, &e1rm)| ProgressPoint { date: date(i as u32 + 1), e1rm, tonnage: 0.0, sets: 1, top_set: None })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn single_rep_is_the_load() {
+        for formula in E1rmFormula::ALL {
+            assert_eq!(formula.estimate(100.0, 1), Some(100.0));
+        }
+    }
+
+    #[test]
+    fn formulas_agree_at_ten_reps() {
+        let epley = E1rmFormula::Epley.estimate(100.0, 10).unwrap();
+        let brzycki = E1rmFormula::Brzycki.estimate(100.0, 10).unwrap();
+        assert!(close(epley, 100.0 * 40.0 / 30.0));
+        assert!(close(brzycki, 100.0 * 36.0 / 27.0));
+    }
+
+    #[test]
+    fn high_reps_and_empty_sets_give_no_estimate() {
+        for formula in E1rmFormula::ALL {
+            assert!(formula.estimate(100.0, 12).is_some());
+            assert_eq!(formula.estimate(100.0, 13), None);
+            // Brzycki divides by 37 - reps
+            assert_eq!(formula.estimate(100.0, 36), None);
+            assert_eq!(formula.estimate(100.0, 37), None);
+            assert_eq!(formula.estimate(100.0, 0), None);
+            assert_eq!(formula.estimate(0.0, 5), None);
+            assert_eq!(formula.estimate(-10.0, 5), None);
+        }
+        assert!(close(E1rmFormula::Brzycki.estimate(100.0, 12).unwrap(), 144.0));
+    }
+
+    #[test]
+    fn formula_names_parse_case_insensitively() {
+        assert_eq!("EPLEY".parse(), Ok(E1rmFormula::Epley));
+        assert_eq!("brzycki".parse(), Ok(E1rmFormula::Brzycki));
+        assert_eq!("lombardi".parse::<E1rmFormula>(), Err(()));
+    }
+
+    #[test]
+    fn stall_needs_more_sessions_than_the_window() {
+        assert_eq!(progress(&[Some(100.0), Some(99.0), Some(98.0)]).stall(3), None);
+        assert_eq!(progress(&[Some(100.0), Some(99.0)]).stall(0), None);
+
+        let stall = progress(&[Some(100.0), Some(99.0), Some(98.0), Some(97.0)]).stall(3).unwrap();
+        assert_eq!((stall.best, stall.best_date, stall.sessions), (100.0, date(1), 3));
+    }
+
+    #[test]
+    fn stall_margin_boundary() {
+        // Within 1% of the best is still a stall, beyond it is progress
+        assert!(progress(&[Some(100.0), Some(101.0), Some(100.5)]).stall(2).is_some());
+        assert_eq!(progress(&[Some(100.0), Some(99.0), Some(101.5)]).stall(2), None);
+    }
+
+    #[test]
+    fn stall_counts_from_the_first_best() {
+        let stall = progress(&[Some(90.0), Some(100.0), Some(100.0), Some(95.0), Some(98.0)]).stall(2).unwrap();
+        assert_eq!(stall.best_date, date(2));
+        assert_eq!(stall.sessions, 3);
+    }
+
+    #[test]
+    fn stall_skips_days_without_an_estimate() {
+        let stall = progress(&[Some(100.0), None, Some(99.0), None, Some(98.0)]).stall(2).unwrap();
+        assert_eq!(stall.sessions, 2);
+        assert_eq!(progress(&[Some(100.0), None, None, Some(99.0)]).stall(2), None);
+    }
+
+    #[test]
+    fn sessions_on_the_same_day_are_combined() {
+        let log = TrainingLog {
+            entries: vec![
+                session(1, 0, vec![set(5, 100.0, Some(2))]),
+                session(1, 0, vec![set(10, 90.0, Some(0)), set(20, 50.0, None)]),
+                session(3, 0, vec![set(8, 95.0, None)]),
+            ],
+            ..TrainingLog::default()
+        };
+        let progress = log.progress(E1rmFormula::Epley);
+        let [exercise] = progress.as_slice() else {
+            panic!("expected one exercise, got {:?}", progress);
+        };
+        let first = &exercise.points[0];
+        assert_eq!((first.date, first.sets), (date(1), 3));
+        assert!(close(first.tonnage, 500.0 + 900.0 + 1000.0));
+        // 90 x 10 beats 100 x 5; the 20 rep set has no estimate
+        assert!(close(first.e1rm.unwrap(), 120.0));
+        assert_eq!(first.top_set, Some(set(10, 90.0, Some(0))));
+        assert_eq!(exercise.points[1].date, date(3));
+    }
+
+    #[test]
+    fn weekly_volume_starts_on_monday_and_counts_hard_sets() {
+        let mut biceps = TargetMuscle::new("biceps".to_string(), MuscleRole::Primary);
+        biceps.muscle_divisions.push(MuscleDivision { name: "long head".to_string(), emphasis: Emphasis::High });
+        let library = Library {
+            entries: vec![LibraryEntry {
+                path: PathBuf::from("curl.json"),
+                exercise: Exercise {
+                    id: 0,
+                    r#type: ExerciseType::Isolation,
+                    target_muscles: TargetMuscles { muscles: vec![biceps] },
+                    ..Exercise::default()
+                },
+            }],
+            ..Library::default()
+        };
+        // 2024-05-05 is a Sunday, 05-06 the following Monday
+        let log = TrainingLog {
+            entries: vec![
+                session(1, 0, vec![set(10, 20.0, Some(2)), set(10, 20.0, Some(6)), set(10, 20.0, None)]),
+                session(5, 0, vec![set(10, 20.0, Some(4))]),
+                session(6, 0, vec![set(10, 20.0, Some(1))]),
+                session(6, 7, vec![set(10, 20.0, Some(1))]),
+            ],
+            ..TrainingLog::default()
+        };
+
+        let weeks = log.weekly_muscle_volume(&library, &VolumeConfig::builtin());
+        let weeks: Vec<(NaiveDate, f64)> = weeks.iter().map(|(start, muscles)| (*start, muscles["biceps"])).collect();
+        assert_eq!(weeks, [(date(6) - Duration::days(7), 3.0), (date(6), 1.0)]);
+    }
+}
@@ -0,0 +1,343 @@
+//! External torque on the moving joint of an exercise, calculated from how
+//! the load is applied rather than measured.
+//!
+//! The model is two-dimensional and lives in the exercise's plane of motion.
+//! Directions of the moving segment and of the line of pull are given in
+//! degrees from straight down as seen in that plane, so 90 is horizontal and
+//! 180 is straight up.
+
+use crate::models::*;
+use crate::resistance::interpolate;
+
+/// Spacing of the joint angles the model is evaluated at.
+const SAMPLE_STEP: u32 = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadSource {
+    /// A dumbbell, barbell or bodyweight load pulled straight down by gravity.
+    FreeWeight,
+    /// A cable whose line of pull keeps the same direction through the rep.
+    Cable { pull_direction: f64 },
+    /// A machine whose cam sets the moment arm at each joint angle.
+    Machine { cam: Vec<CamPoint> },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CamPoint {
+    pub angle: u32,
+    pub moment_arm: f64,
+}
+
+/// How the body is oriented, which decides whether gravity acts in the plane
+/// of motion at all.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Posture {
+    #[default]
+    Upright,
+    /// Lying on the back or front.
+    Lying,
+    SideLying,
+}
+
+impl Posture {
+    /// Whether the vertical lies in `plane` for a body in this posture.
+    pub fn gravity_in_plane(self, plane: &PlaneOfMotion) -> bool {
+        matches!(
+            (self, plane),
+            (Posture::Upright, PlaneOfMotion::Sagittal | PlaneOfMotion::Frontal)
+                | (Posture::Lying, PlaneOfMotion::Sagittal | PlaneOfMotion::Transverse)
+                | (Posture::SideLying, PlaneOfMotion::Frontal | PlaneOfMotion::Transverse)
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TorqueModel {
+    pub source: LoadSource,
+    /// Force the load applies, in any unit.
+    pub load: f64,
+    /// Distance from the joint to where the load is held or attached.
+    /// Unused for machines, whose cam gives the moment arm directly.
+    pub lever_length: f64,
+    pub posture: Posture,
+    /// Direction of the moving segment at `angle_initial`.
+    pub segment_initial: f64,
+    /// Direction of the moving segment at `angle_final`; `None` assumes the
+    /// segment turns by the joint's range of motion, away from straight down.
+    pub segment_final: Option<f64>,
+}
+
+impl Default for TorqueModel {
+    fn default() -> Self {
+        Self {
+            source: LoadSource::FreeWeight,
+            load: 1.0,
+            lever_length: 1.0,
+            posture: Posture::default(),
+            segment_initial: 0.0,
+            segment_final: None,
+        }
+    }
+}
+
+/// Where in the range of motion something happens. The start of the range
+/// (`angle_initial`) is taken to be the stretched position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangePosition {
+    Stretched,
+    MidRange,
+    Shortened,
+}
+
+impl RangePosition {
+    pub fn label(self) -> &'static str {
+        match self {
+            RangePosition::Stretched => "stretched position",
+            RangePosition::MidRange => "mid-range",
+            RangePosition::Shortened => "shortened position",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TorquePoint {
+    pub angle: u32,
+    /// Moment arm of the load, negative where it helps the movement instead of resisting it.
+    pub moment_arm: f64,
+    /// Resisting torque; zero where the load helps the movement.
+    pub torque: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TorqueAnalysis {
+    pub points: Vec<TorquePoint>,
+    pub profile: Option<ResistanceProfile>,
+    pub peak: Option<TorquePoint>,
+    pub peak_position: Option<RangePosition>,
+}
+
+impl TorqueModel {
+    /// Evaluates the model across the range of a dynamic joint. `None` for
+    /// static joints and joints without both angles.
+    pub fn analyze(&self, joint: &Joint, plane: &PlaneOfMotion) -> Option<TorqueAnalysis> {
+        if !joint.dynamic {
+            return None;
+        }
+        let from = joint.angle_initial?;
+        let to = joint.angle_final?;
+
+        let range = from.abs_diff(to);
+        let count = (range / SAMPLE_STEP) as usize + 1 + usize::from(range % SAMPLE_STEP != 0);
+        let points: Vec<TorquePoint> = (0..count)
+            .map(|i| {
+                let offset = (i as u32 * SAMPLE_STEP).min(range);
+                let angle = if to >= from { from + offset } else { from - offset };
+                let moment_arm = self.moment_arm(angle, from, to, plane);
+                TorquePoint {
+                    angle,
+                    moment_arm,
+                    torque: (self.load * moment_arm).max(0.0),
+                }
+            })
+            .collect();
+
+        let peak = points
+            .iter()
+            .copied()
+            .filter(|p| p.torque > 0.0)
+            .max_by(|a, b| a.torque.total_cmp(&b.torque));
+        let peak_position = peak.map(|p| {
+            let progress = if range == 0 { 0.0 } else { p.angle.abs_diff(from) as f64 / range as f64 };
+            if progress < 1.0 / 3.0 {
+                RangePosition::Stretched
+            } else if progress > 2.0 / 3.0 {
+                RangePosition::Shortened
+            } else {
+                RangePosition::MidRange
+            }
+        });
+
+        let mut analysis = TorqueAnalysis {
+            points,
+            profile: None,
+            peak,
+            peak_position,
+        };
+        analysis.profile = analysis.to_curve().classify(from, to);
+        Some(analysis)
+    }
+
+    /// Moment arm of the load at `angle` for a joint moving from `from` to `to`.
+    pub fn moment_arm(&self, angle: u32, from: u32, to: u32, plane: &PlaneOfMotion) -> f64 {
+        match &self.source {
+            LoadSource::FreeWeight => {
+                if self.posture.gravity_in_plane(plane) {
+                    self.lever_moment_arm(angle, from, to, 0.0)
+                } else {
+                    0.0
+                }
+            }
+            LoadSource::Cable { pull_direction } => self.lever_moment_arm(angle, from, to, *pull_direction),
+            LoadSource::Machine { cam } => {
+                let mut points: Vec<(f64, f64)> = cam.iter().map(|p| (p.angle as f64, p.moment_arm)).collect();
+                points.sort_by(|a, b| a.0.total_cmp(&b.0));
+                let (Some(first), Some(last)) = (points.first(), points.last()) else {
+                    return 0.0;
+                };
+                // Hold the cam's end values outside the angles it lists
+                let angle = (angle as f64).clamp(first.0, last.0);
+                interpolate(&points, angle).unwrap_or(0.0)
+            }
+        }
+    }
+
+    /// Moment arm of a force pulling along `pull_direction` on a lever that
+    /// turns from `segment_initial` to `segment_final` over the range.
+    fn lever_moment_arm(&self, angle: u32, from: u32, to: u32, pull_direction: f64) -> f64 {
+        let range = from.abs_diff(to) as f64;
+        let segment_final = self.segment_final.unwrap_or(self.segment_initial + range);
+        let progress = if range == 0.0 { 0.0 } else { angle.abs_diff(from) as f64 / range };
+        let segment = self.segment_initial + (segment_final - self.segment_initial) * progress;
+        // The load resists when it pulls the segment back the way it came
+        let turning = if segment_final >= self.segment_initial { 1.0 } else { -1.0 };
+        self.lever_length * (segment - pull_direction).to_radians().sin() * turning
+    }
+}
+
+impl TorqueAnalysis {
+    /// The calculated torque as a curve that can be stored on the joint.
+    pub fn to_curve(&self) -> ResistanceCurve {
+        ResistanceCurve {
+            samples: self
+                .points
+                .iter()
+                .map(|p| TorqueSample { angle: p.angle, torque: p.torque })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moving_joint(from: u32, to: u32) -> Joint {
+        Joint {
+            name: "elbow".to_string(),
+            dynamic: true,
+            angle: None,
+            direction: Some(JointAction::Flexion),
+            unmapped_direction: None,
+            angle_initial: Some(from),
+            angle_final: Some(to),
+            resistance_curve: None,
+        }
+    }
+
+    fn torque_at(analysis: &TorqueAnalysis, angle: u32) -> f64 {
+        analysis.points.iter().find(|p| p.angle == angle).expect("a sample at that angle").torque
+    }
+
+    #[test]
+    fn free_weight_curl_peaks_with_the_forearm_horizontal() {
+        // Standing curl: the forearm hangs straight down at 180° and turns up by the range
+        let model = TorqueModel { load: 10.0, lever_length: 0.3, ..TorqueModel::default() };
+        let analysis = model.analyze(&moving_joint(180, 40), &PlaneOfMotion::Sagittal).unwrap();
+
+        let peak = analysis.peak.unwrap();
+        assert_eq!(peak.angle, 90);
+        assert!((peak.torque - 3.0).abs() < 1e-9);
+        assert!(torque_at(&analysis, 180).abs() < 1e-9);
+        assert_eq!(analysis.peak_position, Some(RangePosition::MidRange));
+        assert_eq!(analysis.profile, Some(ResistanceProfile::Bell));
+    }
+
+    #[test]
+    fn cable_pulls_along_a_fixed_direction_in_any_posture() {
+        let model = TorqueModel { source: LoadSource::Cable { pull_direction: 40.0 }, ..TorqueModel::default() };
+        let joint = moving_joint(180, 40);
+        let analysis = model.analyze(&joint, &PlaneOfMotion::Sagittal).unwrap();
+
+        // Square to the cable 130° into the segment's turn, i.e. at 50° of elbow angle
+        let peak = analysis.peak.unwrap();
+        assert_eq!(peak.angle, 50);
+        assert!((peak.torque - 1.0).abs() < 1e-9);
+        // At the start the cable helps the movement, which resists nothing
+        let start = analysis.points[0];
+        assert!(start.moment_arm < 0.0);
+        assert_eq!(start.torque, 0.0);
+
+        let lying = TorqueModel { posture: Posture::Lying, ..model };
+        assert_eq!(lying.analyze(&joint, &PlaneOfMotion::Frontal).unwrap().points, analysis.points);
+    }
+
+    #[test]
+    fn machine_cam_is_interpolated_and_held_at_its_ends() {
+        let cam = vec![CamPoint { angle: 120, moment_arm: 1.0 }, CamPoint { angle: 60, moment_arm: 0.5 }];
+        let model = TorqueModel { source: LoadSource::Machine { cam }, ..TorqueModel::default() };
+        let plane = PlaneOfMotion::Sagittal;
+        assert!((model.moment_arm(90, 180, 40, &plane) - 0.75).abs() < 1e-9);
+        assert_eq!(model.moment_arm(180, 180, 40, &plane), 1.0);
+        assert_eq!(model.moment_arm(40, 180, 40, &plane), 0.5);
+
+        let empty = TorqueModel { source: LoadSource::Machine { cam: vec![] }, ..TorqueModel::default() };
+        assert_eq!(empty.moment_arm(90, 180, 40, &plane), 0.0);
+    }
+
+    #[test]
+    fn zero_range_of_motion_gives_one_finite_point() {
+        let model = TorqueModel { segment_initial: 90.0, ..TorqueModel::default() };
+        let analysis = model.analyze(&moving_joint(90, 90), &PlaneOfMotion::Sagittal).unwrap();
+
+        assert_eq!(analysis.points.len(), 1);
+        let point = analysis.points[0];
+        assert!(point.moment_arm.is_finite() && point.torque.is_finite());
+        assert!((point.torque - 1.0).abs() < 1e-9);
+        assert_eq!(analysis.peak_position, Some(RangePosition::Stretched));
+        // One sample is too few to call a profile
+        assert_eq!(analysis.profile, None);
+    }
+
+    #[test]
+    fn gravity_only_loads_planes_that_contain_the_vertical() {
+        assert!(Posture::Upright.gravity_in_plane(&PlaneOfMotion::Frontal));
+        assert!(!Posture::Upright.gravity_in_plane(&PlaneOfMotion::Transverse));
+        assert!(Posture::Lying.gravity_in_plane(&PlaneOfMotion::Transverse));
+        assert!(!Posture::SideLying.gravity_in_plane(&PlaneOfMotion::Sagittal));
+
+        // A standing dumbbell fly moves in the horizontal plane: gravity does nothing
+        let joint = moving_joint(0, 90);
+        let standing = TorqueModel::default().analyze(&joint, &PlaneOfMotion::Transverse).unwrap();
+        assert!(standing.points.iter().all(|p| p.torque == 0.0));
+        assert_eq!((standing.peak, standing.profile), (None, None));
+
+        let lying = TorqueModel { posture: Posture::Lying, ..TorqueModel::default() };
+        assert!(lying.analyze(&joint, &PlaneOfMotion::Transverse).unwrap().peak.is_some());
+    }
+
+    #[test]
+    fn peak_position_follows_the_range_from_the_stretched_end() {
+        let joint = moving_joint(0, 90);
+        let plane = PlaneOfMotion::Sagittal;
+
+        // Segment turning away from horizontal: hardest at the start
+        let from_horizontal = TorqueModel {
+            segment_initial: 90.0,
+            segment_final: Some(180.0),
+            ..TorqueModel::default()
+        };
+        let analysis = from_horizontal.analyze(&joint, &plane).unwrap();
+        assert_eq!(analysis.peak.unwrap().angle, 0);
+        assert_eq!(analysis.peak_position, Some(RangePosition::Stretched));
+        assert_eq!(analysis.profile, Some(ResistanceProfile::Descending));
+
+        // Segment turning up to horizontal: hardest at the end
+        let to_horizontal = TorqueModel { segment_final: Some(90.0), ..TorqueModel::default() };
+        let analysis = to_horizontal.analyze(&joint, &plane).unwrap();
+        assert_eq!(analysis.peak.unwrap().angle, 90);
+        assert_eq!(analysis.peak_position, Some(RangePosition::Shortened));
+        assert_eq!(analysis.profile, Some(ResistanceProfile::Ascending));
+
+        assert_eq!(from_horizontal.analyze(&Joint { dynamic: false, ..joint }, &plane), None);
+    }
+}
@@ -0,0 +1,1160 @@
+use hypertrophy_archive::analytics::E1rmFormula;
+use hypertrophy_archive::biomechanics::{CamPoint, LoadSource, Posture, TorqueModel};
+use hypertrophy_archive::coverage::CoverageMatrix;
+use hypertrophy_archive::library::{
+    backup_file, read_exercise, sanitize_filename, write_atomic, write_exercise, Library, LibraryEntry, StorageError,
+};
+use hypertrophy_archive::migrate;
+use hypertrophy_archive::models::*;
+use hypertrophy_archive::program::{load_programs, Program};
+use hypertrophy_archive::query::ExerciseQuery;
+use hypertrophy_archive::schema::exercise_schema;
+use hypertrophy_archive::taxonomy::Taxonomy;
+use hypertrophy_archive::training_log::TrainingLog;
+use hypertrophy_archive::validation::Severity;
+use hypertrophy_archive::volume::{Volume, VolumeConfig, VolumeStatus};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+const USAGE: &str = "\
+Usage: hypertrophy_archive [--data-dir DIR] <command> [args]
+
+Runs without a window when a command is given.
+
+Commands:
+  list [filters]           List exercises in the data directory
+  show <id|name|file>      Print one exercise as JSON
+  validate [file...]       Validate files (default: the whole library)
+  export [--out FILE]      Write all exercises as one JSON array
+  new --from-json <FILE>   Add an exercise from a JSON file ('-' reads stdin)
+  pack [--out FILE]        Bundle the library into one collection file
+                           (default: archive.json)
+  unpack <FILE> [--overwrite] [--backup]
+                           Write every exercise in a collection file back
+                           out as individual files in the data directory;
+                           --backup keeps replaced files as <name>.json.bak
+  curve <id|name|file>... [--at ANGLE] [--normalize]
+                           Print resistance curves, or the torque of each
+                           exercise at one joint angle
+  stretch <id|name|file>   Score how much each targeted division is loaded
+                           at long length
+  similar <id|name|file> [--limit N] [--equipment EQUIPMENT]
+                           Rank library exercises as substitutes, optionally
+                           only ones with a variant for that equipment
+  torque <id|name|file> [model] [--apply [--backup]]
+                           Calculate the external torque across the range
+                           of the moving joint; --apply stores it as the
+                           joint's resistance curve
+  coverage [--joint NAME] [--missing]
+                           Matrix of joint actions against resistance
+                           profiles and planes, listing the exercises in
+                           each cell, or with --missing the empty cells
+  volume <name|file> [--week N]
+                           Weekly hard sets per muscle and division in a
+                           program from <data-dir>/../programs, compared
+                           with the MEV/MAV/MRV landmarks
+  analytics <e1rm|volume|stalls> [--exercise ID|NAME] [--out FILE]
+                           Progression from the training log in
+                           <data-dir>/log as CSV: estimated 1RM and tonnage
+                           per exercise and day, hard sets per muscle and
+                           week, or exercises without a new best e1RM in
+                           the last --stall-sessions N sessions (default: 3);
+                           --formula epley or brzycki (default: epley)
+  schema [--out FILE]      Print the JSON Schema for exercise files
+  taxonomy                 Print the muscles and joints in use, including
+                           additions from <data-dir>/config/taxonomy.json
+  help                     Show this message
+
+Options:
+  --data-dir DIR           Exercise directory (default: data)
+
+Filters for list:
+  --muscle NAME            Target muscle
+  --role ROLE              primary, secondary or stabilizer
+  --division NAME          Muscle division with some emphasis
+  --joint NAME             Joint used, dynamic or static
+  --dynamic-joint NAME     Joint moved through a range
+  --static-joint NAME      Joint held at a fixed angle
+  --action ACTION          Joint action, e.g. flexion or horizontal_adduction
+  --plane PLANE            sagittal, frontal or transverse
+  --profile PROFILE        ascending, descending, bell or constant, for the
+                           exercise or any of its equipment variants
+  --equipment EQUIPMENT    dumbbell, barbell, cable, machine, band or
+                           bodyweight variant (with --profile: one that
+                           produces that profile)
+  --type TYPE              compound or isolation
+  --text TEXT              Text in the name, description or tips
+  --min-stretch PERCENT    Some active division is loaded at long length
+                           at least this much (see stretch)
+
+Model for torque (directions in degrees from straight down):
+  --joint NAME             Joint to model (default: the first dynamic joint)
+  --source SOURCE          free-weight, cable or machine (default: free-weight)
+  --load FORCE             Force of the load (default: 1)
+  --lever LENGTH           Distance from the joint to the load (default: 1)
+  --posture POSTURE        upright, lying or side-lying (default: upright)
+  --segment-start DEG      Direction of the moving segment at the start
+                           (default: 0)
+  --segment-end DEG        Direction at the end (default: start plus the
+                           joint's range of motion)
+  --pull DEG               Cable line of pull (default: 0)
+  --cam ANGLE:ARM,...      Machine moment arm at joint angles";
+
+const QUERY_FLAGS: &[&str] = &[
+    "--muscle",
+    "--role",
+    "--division",
+    "--joint",
+    "--dynamic-joint",
+    "--static-joint",
+    "--action",
+    "--plane",
+    "--profile",
+    "--equipment",
+    "--type",
+    "--text",
+    "--min-stretch",
+];
+
+const TORQUE_FLAGS: &[&str] = &[
+    "--joint",
+    "--source",
+    "--load",
+    "--lever",
+    "--posture",
+    "--segment-start",
+    "--segment-end",
+    "--pull",
+    "--cam",
+];
+
+#[derive(Debug)]
+pub enum CliError {
+    Usage(String),
+    Io(io::Error),
+    Storage(StorageError),
+    Failed(String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Usage(msg) => write!(f, "{}\n\n{}", msg, USAGE),
+            CliError::Io(e) => write!(f, "{}", e),
+            CliError::Storage(e) => write!(f, "{}", e),
+            CliError::Failed(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(e: io::Error) -> Self {
+        CliError::Io(e)
+    }
+}
+
+impl From<StorageError> for CliError {
+    fn from(e: StorageError) -> Self {
+        CliError::Storage(e)
+    }
+}
+
+impl From<serde_json::Error> for CliError {
+    fn from(e: serde_json::Error) -> Self {
+        CliError::Storage(StorageError::Json(e))
+    }
+}
+
+/// Runs a headless command and returns the process exit code.
+pub fn run(args: &[String]) -> i32 {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    match dispatch(&mut out, args).and_then(|()| Ok(out.flush()?)) {
+        Ok(()) => 0,
+        // A closed pipe (e.g. `show 0 | head`) just means nobody wants the rest
+        Err(CliError::Io(e)) if e.kind() == io::ErrorKind::BrokenPipe => 0,
+        Err(e @ CliError::Usage(_)) => {
+            eprintln!("error: {}", e);
+            2
+        }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            1
+        }
+    }
+}
+
+fn dispatch(out: &mut dyn Write, args: &[String]) -> Result<(), CliError> {
+    let mut data_dir = PathBuf::from("data");
+    let mut rest = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--data-dir" {
+            let dir = iter
+                .next()
+                .ok_or_else(|| CliError::Usage("--data-dir needs a directory".to_string()))?;
+            data_dir = PathBuf::from(dir);
+        } else {
+            rest.push(arg.as_str());
+        }
+    }
+
+    let (command, rest) = match rest.split_first() {
+        Some((command, rest)) => (*command, rest),
+        None => return Err(CliError::Usage("no command given".to_string())),
+    };
+
+    match command {
+        "list" => {
+            let flags = parse_flags(rest, QUERY_FLAGS, &[])?.no_positional()?;
+            list(out, &data_dir, &query_from_flags(&flags)?)
+        }
+        "show" => match parse_flags(rest, &[], &[])?.positional.as_slice() {
+            [target] => show(out, &data_dir, target),
+            _ => Err(CliError::Usage("show takes exactly one id, name or file".to_string())),
+        },
+        "validate" => validate(out, &data_dir, &parse_flags(rest, &[], &[])?.positional),
+        "export" => {
+            let flags = parse_flags(rest, &["--out"], &[])?.no_positional()?;
+            export(out, &data_dir, flags.value("--out"))
+        }
+        "new" => {
+            let flags = parse_flags(rest, &["--from-json"], &[])?.no_positional()?;
+            match flags.value("--from-json") {
+                Some(source) => new_from_json(out, &data_dir, source),
+                None => Err(CliError::Usage("new requires --from-json <FILE>".to_string())),
+            }
+        }
+        "pack" => {
+            let flags = parse_flags(rest, &["--out"], &[])?.no_positional()?;
+            pack(out, &data_dir, Path::new(flags.value("--out").unwrap_or("archive.json")))
+        }
+        "unpack" => {
+            let flags = parse_flags(rest, &[], &["--overwrite", "--backup"])?;
+            match flags.positional.as_slice() {
+                [source] => unpack(
+                    out,
+                    &data_dir,
+                    Path::new(source),
+                    flags.switch("--overwrite"),
+                    flags.switch("--backup"),
+                ),
+                _ => Err(CliError::Usage("unpack takes exactly one collection file".to_string())),
+            }
+        }
+        "curve" => {
+            let flags = parse_flags(rest, &["--at"], &["--normalize"])?;
+            if flags.positional.is_empty() {
+                return Err(CliError::Usage("curve takes at least one id, name or file".to_string()));
+            }
+            let at = flags.value("--at").map(|v| parse_number("--at", v)).transpose()?;
+            curve(out, &data_dir, &flags.positional, at, flags.switch("--normalize"))
+        }
+        "stretch" => match parse_flags(rest, &[], &[])?.positional.as_slice() {
+            [target] => stretch(out, &data_dir, target),
+            _ => Err(CliError::Usage("stretch takes exactly one id, name or file".to_string())),
+        },
+        "similar" => {
+            let flags = parse_flags(rest, &["--limit", "--equipment"], &[])?;
+            let limit = flags.value("--limit").map(|v| parse_number("--limit", v)).transpose()?;
+            let equipment = flags.value("--equipment").map(|v| parse_enum("--equipment", v)).transpose()?;
+            match flags.positional.as_slice() {
+                [target] => similar(out, &data_dir, target, limit.unwrap_or(10), equipment),
+                _ => Err(CliError::Usage("similar takes exactly one id, name or file".to_string())),
+            }
+        }
+        "torque" => {
+            let flags = parse_flags(rest, TORQUE_FLAGS, &["--apply", "--backup"])?;
+            match flags.positional.as_slice() {
+                [target] => torque(out, &data_dir, target, &flags),
+                _ => Err(CliError::Usage("torque takes exactly one id, name or file".to_string())),
+            }
+        }
+        "analytics" => {
+            let flags = parse_flags(rest, &["--exercise", "--formula", "--stall-sessions", "--out"], &[])?;
+            let formula = match flags.value("--formula") {
+                Some(value) => value
+                    .parse()
+                    .map_err(|_| CliError::Usage(format!("invalid value '{}' for --formula", value)))?,
+                None => E1rmFormula::default(),
+            };
+            let stall_sessions = flags
+                .value("--stall-sessions")
+                .map(|v| parse_number("--stall-sessions", v))
+                .transpose()?
+                .unwrap_or(3);
+            match flags.positional.as_slice() {
+                [report] => analytics(
+                    out,
+                    &data_dir,
+                    report,
+                    flags.value("--exercise"),
+                    formula,
+                    stall_sessions,
+                    flags.value("--out"),
+                ),
+                _ => Err(CliError::Usage("analytics takes one report: e1rm, volume or stalls".to_string())),
+            }
+        }
+        "schema" => {
+            let flags = parse_flags(rest, &["--out"], &[])?.no_positional()?;
+            schema(out, &data_dir, flags.value("--out"))
+        }
+        "coverage" => {
+            let flags = parse_flags(rest, &["--joint"], &["--missing"])?.no_positional()?;
+            coverage(out, &data_dir, flags.value("--joint"), flags.switch("--missing"))
+        }
+        "volume" => {
+            let flags = parse_flags(rest, &["--week"], &[])?;
+            let week = flags.value("--week").map(|v| parse_number("--week", v)).transpose()?;
+            match flags.positional.as_slice() {
+                [target] => volume(out, &data_dir, target, week),
+                _ => Err(CliError::Usage("volume takes exactly one program name or file".to_string())),
+            }
+        }
+        "taxonomy" => {
+            parse_flags(rest, &[], &[])?.no_positional()?;
+            let taxonomy = Taxonomy::for_data_dir(&data_dir)?;
+            writeln!(out, "{}", serde_json::to_string_pretty(&taxonomy)?)?;
+            Ok(())
+        }
+        "help" | "--help" | "-h" => {
+            writeln!(out, "{}", USAGE)?;
+            Ok(())
+        }
+        other => Err(CliError::Usage(format!("unknown command '{}'", other))),
+    }
+}
+
+/// Arguments left after the command name, split into `--flag value` pairs,
+/// bare `--switch`es and positional arguments.
+struct Flags<'a> {
+    values: Vec<(&'a str, &'a str)>,
+    switches: Vec<&'a str>,
+    positional: Vec<&'a str>,
+}
+
+impl<'a> Flags<'a> {
+    fn value(&self, flag: &str) -> Option<&'a str> {
+        self.values.iter().find(|(name, _)| *name == flag).map(|(_, value)| *value)
+    }
+
+    fn switch(&self, flag: &str) -> bool {
+        self.switches.contains(&flag)
+    }
+
+    fn no_positional(self) -> Result<Self, CliError> {
+        match self.positional.first() {
+            Some(arg) => Err(CliError::Usage(format!("unexpected argument '{}'", arg))),
+            None => Ok(self),
+        }
+    }
+}
+
+fn parse_flags<'a>(
+    args: &[&'a str],
+    value_flags: &[&str],
+    switch_flags: &[&str],
+) -> Result<Flags<'a>, CliError> {
+    let mut flags = Flags {
+        values: Vec::new(),
+        switches: Vec::new(),
+        positional: Vec::new(),
+    };
+    let mut iter = args.iter();
+    while let Some(&arg) = iter.next() {
+        if value_flags.contains(&arg) {
+            let value = iter
+                .next()
+                .ok_or_else(|| CliError::Usage(format!("{} needs a value", arg)))?;
+            flags.values.push((arg, value));
+        } else if switch_flags.contains(&arg) {
+            flags.switches.push(arg);
+        } else if arg.starts_with("--") {
+            return Err(CliError::Usage(format!("unknown option '{}'", arg)));
+        } else {
+            flags.positional.push(arg);
+        }
+    }
+    Ok(flags)
+}
+
+fn load_library(data_dir: &Path) -> Result<Library, CliError> {
+    let library = Library::load(data_dir)?;
+    for failure in &library.failures {
+        eprintln!("warning: skipping {}: {}", failure.path.display(), failure.error);
+    }
+    Ok(library)
+}
+
+fn query_from_flags(flags: &Flags) -> Result<ExerciseQuery, CliError> {
+    let mut query = ExerciseQuery {
+        muscle_name: flags.value("--muscle").map(str::to_string),
+        division: flags.value("--division").map(str::to_string),
+        joint: flags.value("--joint").map(str::to_string),
+        text: flags.value("--text").map(str::to_string),
+        ..Default::default()
+    };
+    if let Some(joint) = flags.value("--dynamic-joint") {
+        query.joint = Some(joint.to_string());
+        query.joint_dynamic = Some(true);
+    }
+    if let Some(joint) = flags.value("--static-joint") {
+        query.joint = Some(joint.to_string());
+        query.joint_dynamic = Some(false);
+    }
+    query.joint_action = flags
+        .value("--action")
+        .map(|v| v.parse().map_err(|_| CliError::Usage(format!("invalid value '{}' for --action", v))))
+        .transpose()?;
+    query.min_stretch = flags
+        .value("--min-stretch")
+        .map(|v| parse_number::<f64>("--min-stretch", v).map(|percent| percent / 100.0))
+        .transpose()?;
+    query.muscle_role = flags.value("--role").map(|v| parse_enum("--role", v)).transpose()?;
+    query.plane_of_motion = flags.value("--plane").map(|v| parse_enum("--plane", v)).transpose()?;
+    query.resistance_profile = flags.value("--profile").map(|v| parse_enum("--profile", v)).transpose()?;
+    query.equipment = flags.value("--equipment").map(|v| parse_enum("--equipment", v)).transpose()?;
+    query.exercise_type = flags.value("--type").map(|v| parse_enum("--type", v)).transpose()?;
+    Ok(query)
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, CliError> {
+    value
+        .parse()
+        .map_err(|_| CliError::Usage(format!("invalid value '{}' for {}", value, flag)))
+}
+
+/// Parses one of the lowercase enum names used in exercise files.
+fn parse_enum<T: DeserializeOwned>(flag: &str, value: &str) -> Result<T, CliError> {
+    serde_json::from_value(Value::String(value.to_lowercase()))
+        .map_err(|_| CliError::Usage(format!("invalid value '{}' for {}", value, flag)))
+}
+
+fn list(out: &mut dyn Write, data_dir: &Path, query: &ExerciseQuery) -> Result<(), CliError> {
+    let library = load_library(data_dir)?;
+    let taxonomy = Taxonomy::for_data_dir(data_dir)?;
+    for entry in library.search(query, &taxonomy) {
+        let exercise = &entry.exercise;
+        writeln!(
+            out,
+            "{}\t{}\t{:?}\t{}\t{:?}\t{:?}\t{}",
+            exercise.id,
+            exercise.name,
+            exercise.r#type,
+            exercise.target_muscles.primary_name(),
+            exercise.resistance_profile,
+            exercise.plane_of_motion,
+            entry.path.display()
+        )?;
+    }
+    Ok(())
+}
+
+fn show(out: &mut dyn Write, data_dir: &Path, target: &str) -> Result<(), CliError> {
+    let exercise = if Path::new(target).is_file() {
+        read_exercise(Path::new(target))?
+    } else {
+        find_entry(&load_library(data_dir)?, target)?.exercise.clone()
+    };
+
+    writeln!(out, "{}", serde_json::to_string_pretty(&exercise)?)?;
+    Ok(())
+}
+
+/// Looks `target` up in the library by id, or by name if it is not a number.
+fn find_entry<'a>(library: &'a Library, target: &str) -> Result<&'a LibraryEntry, CliError> {
+    let entry = match target.parse::<u32>() {
+        Ok(id) => library.find_by_id(id),
+        Err(_) => library.find_by_name(target),
+    };
+    entry.ok_or_else(|| CliError::Failed(format!("no exercise matches '{}'", target)))
+}
+
+fn curve(
+    out: &mut dyn Write,
+    data_dir: &Path,
+    targets: &[&str],
+    at: Option<f64>,
+    normalize: bool,
+) -> Result<(), CliError> {
+    let library = if targets.iter().all(|t| Path::new(t).is_file()) {
+        Library::default()
+    } else {
+        load_library(data_dir)?
+    };
+
+    for target in targets {
+        let exercise = if Path::new(target).is_file() {
+            read_exercise(Path::new(target))?
+        } else {
+            find_entry(&library, target)?.exercise.clone()
+        };
+        let Some(joint) = exercise.curve_joint() else {
+            return Err(CliError::Failed(format!("'{}' has no resistance curve", exercise.name)));
+        };
+        let mut curve = joint.resistance_curve.clone().unwrap_or_default();
+        if normalize {
+            curve = curve.normalized();
+        }
+
+        match at {
+            Some(angle) => match curve.torque_at(angle) {
+                Some(torque) => writeln!(out, "{}\t{}\t{:.3}", exercise.name, joint.name, torque)?,
+                None => writeln!(out, "{}\t{}\toutside sampled range", exercise.name, joint.name)?,
+            },
+            None => {
+                let profile = joint
+                    .derived_resistance_profile()
+                    .map_or("unclassified".to_string(), |p| format!("{:?}", p));
+                writeln!(
+                    out,
+                    "{} ({} {}, {})",
+                    exercise.name,
+                    joint.name,
+                    joint.direction.map_or("?", |a| a.label()),
+                    profile
+                )?;
+                for sample in curve.sorted() {
+                    writeln!(out, "{}\t{:.3}", sample.angle, sample.torque)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn stretch(out: &mut dyn Write, data_dir: &Path, target: &str) -> Result<(), CliError> {
+    let exercise = if Path::new(target).is_file() {
+        read_exercise(Path::new(target))?
+    } else {
+        find_entry(&load_library(data_dir)?, target)?.exercise.clone()
+    };
+    let scores = exercise.stretch_scores(&Taxonomy::for_data_dir(data_dir)?);
+    if scores.is_empty() {
+        return Err(CliError::Failed(format!(
+            "no length model covers the divisions and joints of '{}'",
+            exercise.name
+        )));
+    }
+    for score in scores {
+        writeln!(
+            out,
+            "{}\t{}\t{:.0}%\t(reaches {:.0}% of full length)",
+            score.muscle,
+            score.division,
+            score.score * 100.0,
+            score.longest * 100.0
+        )?;
+    }
+    Ok(())
+}
+
+fn coverage(out: &mut dyn Write, data_dir: &Path, joint: Option<&str>, missing: bool) -> Result<(), CliError> {
+    let library = load_library(data_dir)?;
+    let mut matrix = CoverageMatrix::build(&library, &Taxonomy::for_data_dir(data_dir)?);
+    if let Some(joint) = joint {
+        matrix.rows.retain(|r| r.joint.eq_ignore_ascii_case(joint));
+        if matrix.rows.is_empty() {
+            return Err(CliError::Failed(format!("no joint actions for '{}'", joint)));
+        }
+    }
+
+    let width = matrix.rows.iter().map(|r| r.label().len()).max().unwrap_or(0).max(6);
+    let profiles: String = ResistanceProfile::ALL
+        .iter()
+        .map(|p| format!("{:<3}", p.label()[..1].to_uppercase()))
+        .collect();
+    write!(out, "{:width$}", "", width = width)?;
+    for plane in PlaneOfMotion::ALL {
+        write!(out, "  {:<12}", plane.label())?;
+    }
+    writeln!(out)?;
+    writeln!(out, "{:width$}{}", "action", format!("  {}", profiles).repeat(PlaneOfMotion::ALL.len()), width = width)?;
+    for row in &matrix.rows {
+        write!(out, "{:width$}", row.label(), width = width)?;
+        for plane in PlaneOfMotion::ALL {
+            write!(out, "  ")?;
+            for profile in ResistanceProfile::ALL {
+                let count = row.cell(&plane, &profile).map_or(0, |c| c.exercises.len());
+                let mark = if count == 0 { ".".to_string() } else { count.to_string() };
+                write!(out, "{:<3}", mark)?;
+            }
+        }
+        writeln!(out)?;
+    }
+    writeln!(
+        out,
+        "{} of {} cells filled (A ascending, D descending, B bell, C constant)",
+        matrix.filled(),
+        matrix.cell_count()
+    )?;
+    writeln!(out)?;
+
+    for row in &matrix.rows {
+        if missing {
+            let empty: Vec<String> = row
+                .cells
+                .iter()
+                .filter(|c| c.is_empty())
+                .map(|c| format!("{} {}", c.plane.label(), c.profile.label()))
+                .collect();
+            if !empty.is_empty() {
+                writeln!(out, "{}: {}", row.label(), empty.join(", "))?;
+            }
+            continue;
+        }
+        for cell in row.cells.iter().filter(|c| !c.is_empty()) {
+            let exercises: Vec<String> = cell.exercises.iter().map(|e| e.label()).collect();
+            writeln!(
+                out,
+                "{}, {}, {}: {}",
+                row.label(),
+                cell.plane.label(),
+                cell.profile.label(),
+                exercises.join(", ")
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// A program file, or the program with that name or file name in the programs folder.
+fn find_program(data_dir: &Path, target: &str) -> Result<(PathBuf, Program), CliError> {
+    if Path::new(target).is_file() {
+        return Ok((PathBuf::from(target), Program::load(Path::new(target))?));
+    }
+    for (path, program) in load_programs(&Program::dir_for_data_dir(data_dir))? {
+        let stem_matches = path.file_stem().is_some_and(|stem| stem == target);
+        if let Ok(program) = program
+            && (stem_matches || program.name.eq_ignore_ascii_case(target))
+        {
+            return Ok((path, program));
+        }
+    }
+    Err(CliError::Failed(format!("no program matches '{}'", target)))
+}
+
+fn volume(out: &mut dyn Write, data_dir: &Path, target: &str, week: Option<usize>) -> Result<(), CliError> {
+    let (_, program) = find_program(data_dir, target)?;
+    let library = load_library(data_dir)?;
+    let config = VolumeConfig::for_data_dir(data_dir)?;
+
+    let unknown = program.unknown_exercise_ids(&library);
+    if !unknown.is_empty() {
+        let ids: Vec<String> = unknown.iter().map(|id| format!("#{}", id)).collect();
+        eprintln!("warning: not in the library, skipped: {}", ids.join(", "));
+    }
+
+    let weeks = program.weekly_volume(&library, &config);
+    if let Some(week) = week
+        && !(1..=weeks.len()).contains(&week)
+    {
+        return Err(CliError::Failed(format!("'{}' has {} week(s)", program.name, weeks.len())));
+    }
+    for volume in weeks.iter().filter(|v| week.is_none_or(|w| w == v.week)) {
+        writeln!(
+            out,
+            "{}, week {}{}",
+            program.name,
+            volume.week,
+            if volume.deload { " (deload)" } else { "" }
+        )?;
+        writeln!(out, "muscle	division	sets	MEV	MAV	MRV	status")?;
+        for muscle in &volume.muscles {
+            print_volume(out, &muscle.muscle.name, "", &muscle.muscle)?;
+            for division in &muscle.divisions {
+                print_volume(out, &muscle.muscle.name, &division.name, division)?;
+            }
+        }
+        for (status, heading) in [(VolumeStatus::BelowMev, "under-dosed"), (VolumeStatus::AboveMrv, "over-dosed")] {
+            let names = volume.with_status(status);
+            if !names.is_empty() {
+                writeln!(out, "{}: {}", heading, names.join(", "))?;
+            }
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+fn print_volume(out: &mut dyn Write, muscle: &str, division: &str, volume: &Volume) -> io::Result<()> {
+    let landmarks = volume.landmarks.as_ref().map_or("-\t-\t-".to_string(), |l| {
+        format!("{}\t{}\t{}", l.mev, l.mav, l.mrv)
+    });
+    writeln!(
+        out,
+        "{}\t{}\t{:.1}\t{}\t{}",
+        muscle,
+        division,
+        volume.sets,
+        landmarks,
+        volume.status.map_or("", |s| s.label())
+    )
+}
+
+fn similar(
+    out: &mut dyn Write,
+    data_dir: &Path,
+    target: &str,
+    limit: usize,
+    equipment: Option<Equipment>,
+) -> Result<(), CliError> {
+    let library = load_library(data_dir)?;
+    let (path, exercise) = if Path::new(target).is_file() {
+        (PathBuf::from(target), read_exercise(Path::new(target))?)
+    } else {
+        let entry = find_entry(&library, target)?;
+        (entry.path.clone(), entry.exercise.clone())
+    };
+
+    let substitutes: Vec<_> = library
+        .substitutes(&exercise, Some(&path))
+        .into_iter()
+        .filter(|s| equipment.is_none_or(|eq| s.entry.exercise.variants.iter().any(|v| v.equipment == eq)))
+        .take(limit)
+        .collect();
+    if substitutes.is_empty() {
+        return Err(CliError::Failed(match equipment {
+            Some(equipment) => format!("no other exercise has a {} variant", equipment.label()),
+            None => format!("no other exercise to compare '{}' with", exercise.name),
+        }));
+    }
+    writeln!(out, "score	id	name	muscles	joints	range	profile	plane")?;
+    for substitute in substitutes {
+        let s = substitute.similarity;
+        writeln!(
+            out,
+            "{:.0}%	{}	{}	{:.0}%	{:.0}%	{:.0}%	{:.0}%	{:.0}%",
+            s.score * 100.0,
+            substitute.entry.exercise.id,
+            substitute.entry.exercise.name,
+            s.muscles * 100.0,
+            s.joints * 100.0,
+            s.range * 100.0,
+            s.profile * 100.0,
+            s.plane * 100.0
+        )?;
+    }
+    Ok(())
+}
+
+fn torque_model_from_flags(flags: &Flags) -> Result<TorqueModel, CliError> {
+    let source = match flags.value("--source").unwrap_or("free-weight") {
+        "free-weight" => LoadSource::FreeWeight,
+        "cable" => LoadSource::Cable {
+            pull_direction: flags.value("--pull").map(|v| parse_number("--pull", v)).transpose()?.unwrap_or(0.0),
+        },
+        "machine" => {
+            let cam = flags
+                .value("--cam")
+                .ok_or_else(|| CliError::Usage("--source machine needs --cam".to_string()))?;
+            LoadSource::Machine { cam: parse_cam(cam)? }
+        }
+        other => return Err(CliError::Usage(format!("invalid value '{}' for --source", other))),
+    };
+    let posture = match flags.value("--posture").unwrap_or("upright") {
+        "upright" => Posture::Upright,
+        "lying" => Posture::Lying,
+        "side-lying" => Posture::SideLying,
+        other => return Err(CliError::Usage(format!("invalid value '{}' for --posture", other))),
+    };
+
+    let defaults = TorqueModel::default();
+    Ok(TorqueModel {
+        source,
+        posture,
+        load: flags.value("--load").map(|v| parse_number("--load", v)).transpose()?.unwrap_or(defaults.load),
+        lever_length: flags
+            .value("--lever")
+            .map(|v| parse_number("--lever", v))
+            .transpose()?
+            .unwrap_or(defaults.lever_length),
+        segment_initial: flags
+            .value("--segment-start")
+            .map(|v| parse_number("--segment-start", v))
+            .transpose()?
+            .unwrap_or(defaults.segment_initial),
+        segment_final: flags.value("--segment-end").map(|v| parse_number("--segment-end", v)).transpose()?,
+    })
+}
+
+/// Parses `ANGLE:ARM` pairs separated by commas.
+fn parse_cam(value: &str) -> Result<Vec<CamPoint>, CliError> {
+    value
+        .split(',')
+        .map(|pair| {
+            let (angle, moment_arm) = pair
+                .split_once(':')
+                .ok_or_else(|| CliError::Usage(format!("invalid cam point '{}' (expected ANGLE:ARM)", pair)))?;
+            Ok(CamPoint {
+                angle: parse_number("--cam", angle.trim())?,
+                moment_arm: parse_number("--cam", moment_arm.trim())?,
+            })
+        })
+        .collect()
+}
+
+fn torque(out: &mut dyn Write, data_dir: &Path, target: &str, flags: &Flags) -> Result<(), CliError> {
+    let model = torque_model_from_flags(flags)?;
+    let (path, mut exercise) = if Path::new(target).is_file() {
+        (PathBuf::from(target), read_exercise(Path::new(target))?)
+    } else {
+        let library = load_library(data_dir)?;
+        let entry = find_entry(&library, target)?;
+        (entry.path.clone(), entry.exercise.clone())
+    };
+
+    let joint_index = exercise
+        .joints_involved
+        .joints
+        .iter()
+        .position(|j| j.dynamic && flags.value("--joint").is_none_or(|name| j.name == name))
+        .ok_or_else(|| CliError::Failed(format!("'{}' has no matching dynamic joint", exercise.name)))?;
+    let joint = &exercise.joints_involved.joints[joint_index];
+    let analysis = model
+        .analyze(joint, &exercise.plane_of_motion)
+        .ok_or_else(|| CliError::Failed(format!("the {} needs an initial and a final angle", joint.name)))?;
+
+    writeln!(
+        out,
+        "{} ({} {}, {:?} plane)",
+        exercise.name,
+        joint.name,
+        joint.direction.map_or("?", |a| a.label()),
+        exercise.plane_of_motion
+    )?;
+    writeln!(out, "angle\tmoment_arm\ttorque")?;
+    for point in &analysis.points {
+        writeln!(out, "{}\t{:.3}\t{:.3}", point.angle, point.moment_arm, point.torque)?;
+    }
+    match &analysis.profile {
+        Some(profile) => writeln!(out, "profile: {:?}", profile)?,
+        None => writeln!(out, "profile: none (the load never resists the movement)")?,
+    }
+    if let (Some(peak), Some(position)) = (analysis.peak, analysis.peak_position) {
+        writeln!(out, "peak: {:.3} at {}° ({})", peak.torque, peak.angle, position.label())?;
+    }
+
+    if flags.switch("--apply") {
+        exercise.joints_involved.joints[joint_index].resistance_curve = Some(analysis.to_curve());
+        exercise.sync_resistance_profile();
+        if flags.switch("--backup")
+            && let Some(backup) = backup_file(&path)?
+        {
+            writeln!(out, "Backed up the previous version to {}", backup.display())?;
+        }
+        write_exercise(&path, &exercise)?;
+        writeln!(out, "Stored the curve in {}", path.display())?;
+    }
+    Ok(())
+}
+
+fn validate(out: &mut dyn Write, data_dir: &Path, files: &[&str]) -> Result<(), CliError> {
+    let taxonomy = Taxonomy::for_data_dir(data_dir)?;
+    let mut checked = Vec::new();
+    let mut unreadable = 0;
+
+    if files.is_empty() {
+        let library = Library::load(data_dir)?;
+        for failure in library.failures {
+            writeln!(out, "{}: error: {}", failure.path.display(), failure.error)?;
+            unreadable += 1;
+        }
+        checked.extend(library.entries.into_iter().map(|e| (e.path, e.exercise)));
+    } else {
+        for file in files {
+            match read_exercise(Path::new(file)) {
+                Ok(exercise) => checked.push((PathBuf::from(file), exercise)),
+                Err(e) => {
+                    writeln!(out, "{}: error: {}", file, e)?;
+                    unreadable += 1;
+                }
+            }
+        }
+    }
+
+    let mut invalid = 0;
+    for (path, exercise) in &checked {
+        let report = exercise.validate_with(&taxonomy);
+        for issue in &report.issues {
+            let severity = match issue.severity() {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+            };
+            writeln!(out, "{}: {}: {}", path.display(), severity, issue)?;
+        }
+        if report.has_errors() {
+            invalid += 1;
+        }
+    }
+
+    writeln!(
+        out,
+        "{} file(s) checked, {} invalid, {} unreadable",
+        checked.len() + unreadable,
+        invalid,
+        unreadable
+    )?;
+    if invalid + unreadable > 0 {
+        return Err(CliError::Failed("validation failed".to_string()));
+    }
+    Ok(())
+}
+
+fn export(out: &mut dyn Write, data_dir: &Path, out_file: Option<&str>) -> Result<(), CliError> {
+    let library = load_library(data_dir)?;
+    let exercises: Vec<&Exercise> = library.entries.iter().map(|e| &e.exercise).collect();
+    let json = serde_json::to_string_pretty(&exercises)?;
+
+    match out_file {
+        Some(path) => {
+            write_atomic(Path::new(path), json.as_bytes())?;
+            eprintln!("Exported {} exercises to {}", exercises.len(), path);
+        }
+        None => writeln!(out, "{}", json)?,
+    }
+    Ok(())
+}
+
+fn analytics(
+    out: &mut dyn Write,
+    data_dir: &Path,
+    report: &str,
+    exercise: Option<&str>,
+    formula: E1rmFormula,
+    stall_sessions: usize,
+    out_file: Option<&str>,
+) -> Result<(), CliError> {
+    let library = load_library(data_dir)?;
+    let log = TrainingLog::load(&TrainingLog::dir_for_data_dir(data_dir))?;
+    for failure in &log.failures {
+        eprintln!("warning: skipped {}: {}", failure.path.display(), failure.error);
+    }
+    let exercise_id = exercise.map(|target| find_entry(&library, target).map(|e| e.exercise.id)).transpose()?;
+    let name = |id: u32| library.find_by_id(id).map_or(String::new(), |e| e.exercise.name.clone());
+    let mut progress = log.progress(formula);
+    progress.retain(|p| exercise_id.is_none_or(|id| id == p.exercise_id));
+
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    match report {
+        "e1rm" => {
+            rows.push(
+                ["date", "exercise_id", "exercise", "e1rm", "tonnage", "sets", "top_load", "top_reps"]
+                    .map(String::from)
+                    .to_vec(),
+            );
+            for exercise in &progress {
+                for point in &exercise.points {
+                    rows.push(vec![
+                        point.date.to_string(),
+                        exercise.exercise_id.to_string(),
+                        name(exercise.exercise_id),
+                        point.e1rm.map_or(String::new(), |e1rm| format!("{:.1}", e1rm)),
+                        format!("{:.1}", point.tonnage),
+                        point.sets.to_string(),
+                        point.top_set.map_or(String::new(), |s| s.load.to_string()),
+                        point.top_set.map_or(String::new(), |s| s.reps.to_string()),
+                    ]);
+                }
+            }
+        }
+        "volume" => {
+            let config = VolumeConfig::for_data_dir(data_dir)?;
+            rows.push(["week_start", "muscle", "sets"].map(String::from).to_vec());
+            for (week_start, muscles) in log.weekly_muscle_volume(&library, &config) {
+                for (muscle, sets) in muscles {
+                    rows.push(vec![week_start.to_string(), muscle, format!("{:.2}", sets)]);
+                }
+            }
+        }
+        "stalls" => {
+            rows.push(
+                ["exercise_id", "exercise", "best_e1rm", "best_date", "sessions_since"]
+                    .map(String::from)
+                    .to_vec(),
+            );
+            for stall in progress.iter().filter_map(|p| p.stall(stall_sessions)) {
+                rows.push(vec![
+                    stall.exercise_id.to_string(),
+                    name(stall.exercise_id),
+                    format!("{:.1}", stall.best),
+                    stall.best_date.to_string(),
+                    stall.sessions.to_string(),
+                ]);
+            }
+        }
+        other => return Err(CliError::Usage(format!("unknown analytics report '{}'", other))),
+    }
+
+    let csv: String = rows
+        .iter()
+        .map(|row| row.iter().map(|field| csv_field(field)).collect::<Vec<_>>().join(",") + "\n")
+        .collect();
+    match out_file {
+        Some(path) => {
+            write_atomic(Path::new(path), csv.as_bytes())?;
+            eprintln!("Wrote {} rows to {}", rows.len() - 1, path);
+        }
+        None => write!(out, "{}", csv)?,
+    }
+    Ok(())
+}
+
+/// Quotes a CSV field when it contains a separator, quote or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn schema(out: &mut dyn Write, data_dir: &Path, out_file: Option<&str>) -> Result<(), CliError> {
+    let json = serde_json::to_string_pretty(&exercise_schema(&Taxonomy::for_data_dir(data_dir)?))?;
+    match out_file {
+        Some(path) => {
+            write_atomic(Path::new(path), json.as_bytes())?;
+            eprintln!("Wrote exercise schema to {}", path);
+        }
+        None => writeln!(out, "{}", json)?,
+    }
+    Ok(())
+}
+
+fn new_from_json(out: &mut dyn Write, data_dir: &Path, source: &str) -> Result<(), CliError> {
+    let mut contents = String::new();
+    if source == "-" {
+        io::stdin().read_to_string(&mut contents)?;
+    } else {
+        File::open(source)?.read_to_string(&mut contents)?;
+    }
+    let mut exercise = migrate::parse_exercise(&contents).map_err(StorageError::from)?;
+    exercise.sync_resistance_profile();
+
+    let report = exercise.validate_with(&Taxonomy::for_data_dir(data_dir)?);
+    for issue in &report.issues {
+        eprintln!("{}", issue);
+    }
+    if report.has_errors() {
+        return Err(CliError::Failed(format!(
+            "refusing to add '{}': {} validation error(s)",
+            exercise.name,
+            report.errors().count()
+        )));
+    }
+
+    let path = data_dir.join(format!("{}.json", sanitize_filename(&exercise.name)));
+    if path.exists() {
+        return Err(CliError::Failed(format!("{} already exists", path.display())));
+    }
+
+    let library = load_library(data_dir)?;
+    exercise.id = library.assign_id(exercise.id, &path)?;
+    std::fs::create_dir_all(data_dir)?;
+    write_exercise(&path, &exercise)?;
+    writeln!(out, "Added exercise #{} at {}", exercise.id, path.display())?;
+    Ok(())
+}
+
+fn pack(out: &mut dyn Write, data_dir: &Path, out_file: &Path) -> Result<(), CliError> {
+    let library = load_library(data_dir)?;
+    // A collection with repeated ids could not be unpacked again
+    let duplicates = library.duplicate_ids();
+    if !duplicates.is_empty() {
+        let ids: Vec<String> = duplicates
+            .iter()
+            .map(|(id, paths)| {
+                let files: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+                format!("#{} ({})", id, files.join(", "))
+            })
+            .collect();
+        return Err(CliError::Failed(format!(
+            "ids used by more than one file: {}; renumber them from the library screen first",
+            ids.join("; ")
+        )));
+    }
+    let collection = ExerciseCollection::from_library(&library);
+    collection.save(out_file)?;
+    writeln!(
+        out,
+        "Packed {} exercises ({} compound, {} isolation) into {}",
+        collection.counts.exercises,
+        collection.counts.compound,
+        collection.counts.isolation,
+        out_file.display()
+    )?;
+    Ok(())
+}
+
+fn unpack(out: &mut dyn Write, data_dir: &Path, source: &Path, overwrite: bool, backup: bool) -> Result<(), CliError> {
+    let collection = ExerciseCollection::load(source)?;
+
+    // Work out every target path first so nothing is written if any of them clash
+    let mut targets: Vec<PathBuf> = Vec::new();
+    for exercise in &collection.exercises {
+        let path = data_dir.join(format!("{}.json", sanitize_filename(&exercise.name)));
+        if targets.contains(&path) {
+            return Err(CliError::Failed(format!(
+                "more than one exercise in {} would be written to {}",
+                source.display(),
+                path.display()
+            )));
+        }
+        if path.exists() && !overwrite {
+            return Err(CliError::Failed(format!(
+                "{} already exists (use --overwrite to replace it)",
+                path.display()
+            )));
+        }
+        targets.push(path);
+    }
+
+    let mut collection_ids: HashMap<u32, &str> = HashMap::new();
+    for exercise in &collection.exercises {
+        if let Some(other) = collection_ids.insert(exercise.id, &exercise.name) {
+            return Err(CliError::Failed(format!(
+                "{} uses id #{} for both '{}' and '{}'",
+                source.display(),
+                exercise.id,
+                other,
+                exercise.name
+            )));
+        }
+    }
+
+    // Ids are what programs and logs refer to, so clashes are rejected rather than renumbered.
+    // Files about to be overwritten give up their id.
+    let library = load_library(data_dir)?;
+    let mut ids: HashMap<u32, &Path> = library
+        .entries
+        .iter()
+        .filter(|entry| !targets.contains(&entry.path))
+        .map(|entry| (entry.exercise.id, entry.path.as_path()))
+        .collect();
+    for (exercise, path) in collection.exercises.iter().zip(&targets) {
+        if let Some(other) = ids.insert(exercise.id, path) {
+            return Err(CliError::Failed(format!(
+                "exercise #{} '{}' would share its id with {} in the library",
+                exercise.id,
+                exercise.name,
+                other.display()
+            )));
+        }
+    }
+
+    std::fs::create_dir_all(data_dir)?;
+    for (exercise, path) in collection.exercises.iter().zip(&targets) {
+        if backup {
+            backup_file(path)?;
+        }
+        write_exercise(path, exercise)?;
+    }
+    writeln!(out, "Unpacked {} exercises into {}", targets.len(), data_dir.display())?;
+    Ok(())
+}
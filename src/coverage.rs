@@ -0,0 +1,264 @@
+//! Which combinations of joint action, resistance profile and plane of
+//! motion the library already has an exercise for, and which are missing.
+//!
+//! Rows are the actions of the dynamic joints: every action the taxonomy
+//! lists for a joint, plus any other action an exercise uses. An exercise
+//! fills the cell of its own profile and of each equipment variant's profile.
+
+use crate::library::Library;
+use crate::models::*;
+use crate::taxonomy::Taxonomy;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoveringExercise {
+    /// File the exercise was loaded from, which tells apart exercises that
+    /// share an id until the duplicates are renumbered.
+    pub path: PathBuf,
+    pub id: u32,
+    pub name: String,
+    /// The equipment variant that produces the cell's profile, when the
+    /// exercise's own profile is a different one.
+    pub variant: Option<String>,
+}
+
+impl CoveringExercise {
+    pub fn label(&self) -> String {
+        match &self.variant {
+            Some(variant) => format!("#{} {} via {}", self.id, self.name, variant),
+            None => format!("#{} {}", self.id, self.name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoverageCell {
+    pub profile: ResistanceProfile,
+    pub plane: PlaneOfMotion,
+    pub exercises: Vec<CoveringExercise>,
+}
+
+impl CoverageCell {
+    pub fn is_empty(&self) -> bool {
+        self.exercises.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoverageRow {
+    pub joint: String,
+    pub action: JointAction,
+    /// One cell per plane and profile, planes outermost, both in `ALL` order.
+    pub cells: Vec<CoverageCell>,
+}
+
+impl CoverageRow {
+    pub fn label(&self) -> String {
+        format!("{} {}", self.joint, self.action.label())
+    }
+
+    pub fn cell(&self, plane: &PlaneOfMotion, profile: &ResistanceProfile) -> Option<&CoverageCell> {
+        self.cells.iter().find(|c| c.plane == *plane && c.profile == *profile)
+    }
+
+    pub fn filled(&self) -> usize {
+        self.cells.iter().filter(|c| !c.is_empty()).count()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CoverageMatrix {
+    pub rows: Vec<CoverageRow>,
+}
+
+impl CoverageMatrix {
+    pub fn build(library: &Library, taxonomy: &Taxonomy) -> CoverageMatrix {
+        let mut matrix = CoverageMatrix::default();
+        for spec in &taxonomy.joints {
+            for action in &spec.actions {
+                matrix.row_mut(&spec.name, *action);
+            }
+        }
+
+        for entry in &library.entries {
+            let exercise = &entry.exercise;
+            for joint in exercise.joints_involved.joints.iter().filter(|j| j.dynamic) {
+                let Some(action) = joint.direction else {
+                    continue;
+                };
+                let row = matrix.row_mut(&joint.name, action);
+                let mut fill = |profile: &ResistanceProfile, variant: Option<String>| {
+                    let cell = row
+                        .cells
+                        .iter_mut()
+                        .find(|c| c.plane == exercise.plane_of_motion && c.profile == *profile)
+                        .expect("every row has every cell");
+                    if !cell.exercises.iter().any(|e| e.path == entry.path) {
+                        cell.exercises.push(CoveringExercise {
+                            path: entry.path.clone(),
+                            id: exercise.id,
+                            name: exercise.name.clone(),
+                            variant,
+                        });
+                    }
+                };
+                fill(&exercise.resistance_profile, None);
+                for variant in &exercise.variants {
+                    fill(&variant.resistance_profile, Some(variant.label()));
+                }
+            }
+        }
+        matrix
+    }
+
+    fn row_mut(&mut self, joint: &str, action: JointAction) -> &mut CoverageRow {
+        let index = match self.rows.iter().position(|r| r.joint == joint && r.action == action) {
+            Some(index) => index,
+            None => {
+                let cells = PlaneOfMotion::ALL
+                    .into_iter()
+                    .flat_map(|plane| {
+                        ResistanceProfile::ALL.into_iter().map(move |profile| CoverageCell {
+                            profile,
+                            plane: plane.clone(),
+                            exercises: Vec::new(),
+                        })
+                    })
+                    .collect();
+                self.rows.push(CoverageRow {
+                    joint: joint.to_string(),
+                    action,
+                    cells,
+                });
+                self.rows.len() - 1
+            }
+        };
+        &mut self.rows[index]
+    }
+
+    pub fn cell_count(&self) -> usize {
+        self.rows.iter().map(|r| r.cells.len()).sum()
+    }
+
+    pub fn filled(&self) -> usize {
+        self.rows.iter().map(|r| r.filled()).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::LibraryEntry;
+    use crate::taxonomy::JointSpec;
+    use std::path::Path;
+
+    fn taxonomy() -> Taxonomy {
+        Taxonomy {
+            joints: vec![JointSpec {
+                name: "elbow".to_string(),
+                actions: vec![JointAction::Flexion, JointAction::Extension],
+            }],
+            ..Taxonomy::default()
+        }
+    }
+
+    fn moving(name: &str, action: JointAction) -> Joint {
+        Joint {
+            name: name.to_string(),
+            dynamic: true,
+            angle: None,
+            direction: Some(action),
+            unmapped_direction: None,
+            angle_initial: Some(180),
+            angle_final: Some(40),
+            resistance_curve: None,
+        }
+    }
+
+    fn entry(path: &str, id: u32, joints: Vec<Joint>, profile: ResistanceProfile) -> LibraryEntry {
+        LibraryEntry {
+            path: PathBuf::from(path),
+            exercise: Exercise {
+                id,
+                name: path.trim_end_matches(".json").replace('_', " "),
+                joints_involved: JointsInvolved { joints },
+                resistance_profile: profile,
+                plane_of_motion: PlaneOfMotion::Sagittal,
+                ..Exercise::default()
+            },
+        }
+    }
+
+    fn build(entries: Vec<LibraryEntry>) -> CoverageMatrix {
+        CoverageMatrix::build(&Library { entries, ..Library::default() }, &taxonomy())
+    }
+
+    fn row<'a>(matrix: &'a CoverageMatrix, joint: &str, action: JointAction) -> &'a CoverageRow {
+        matrix.rows.iter().find(|r| r.joint == joint && r.action == action).expect("a row for the action")
+    }
+
+    fn names(row: &CoverageRow, profile: ResistanceProfile) -> Vec<String> {
+        row.cell(&PlaneOfMotion::Sagittal, &profile).unwrap().exercises.iter().map(|e| e.label()).collect()
+    }
+
+    #[test]
+    fn empty_library_has_an_empty_row_per_taxonomy_action() {
+        let matrix = build(vec![]);
+        let rows: Vec<String> = matrix.rows.iter().map(|r| r.label()).collect();
+        assert_eq!(rows, ["elbow flexion", "elbow extension"]);
+        assert_eq!(matrix.cell_count(), 2 * PlaneOfMotion::ALL.len() * ResistanceProfile::ALL.len());
+        assert_eq!(matrix.filled(), 0);
+        assert!(matrix.rows.iter().flat_map(|r| &r.cells).all(|c| c.is_empty()));
+    }
+
+    #[test]
+    fn exercises_fill_the_cells_of_their_action_profile_and_plane() {
+        let variant = |equipment, resistance_profile| EquipmentVariant {
+            equipment,
+            resistance_profile,
+            ..EquipmentVariant::default()
+        };
+        let joints = vec![moving("elbow", JointAction::Flexion)];
+        let mut curl = entry("cable_curl.json", 1, joints, ResistanceProfile::Bell);
+        curl.exercise.variants = vec![
+            variant(Equipment::Cable, ResistanceProfile::Constant),
+            // Same profile as the exercise itself: listed once
+            variant(Equipment::Dumbbell, ResistanceProfile::Bell),
+        ];
+        let matrix = build(vec![curl]);
+
+        let flexion = row(&matrix, "elbow", JointAction::Flexion);
+        assert_eq!(names(flexion, ResistanceProfile::Bell), ["#1 cable curl"]);
+        assert_eq!(names(flexion, ResistanceProfile::Constant), ["#1 cable curl via cable"]);
+        assert!(names(flexion, ResistanceProfile::Ascending).is_empty());
+        assert!(flexion.cell(&PlaneOfMotion::Frontal, &ResistanceProfile::Bell).unwrap().is_empty());
+        assert_eq!(row(&matrix, "elbow", JointAction::Extension).filled(), 0);
+        assert_eq!(matrix.filled(), 2);
+    }
+
+    #[test]
+    fn one_exercise_fills_a_row_for_each_moving_joint() {
+        let joints = vec![moving("elbow", JointAction::Flexion), moving("shoulder", JointAction::Extension)];
+        let row_exercise = entry("row.json", 4, joints, ResistanceProfile::Ascending);
+        let matrix = build(vec![row_exercise]);
+
+        // Actions outside the taxonomy get a row of their own
+        assert_eq!(matrix.rows.len(), 3);
+        for (joint, action) in [("elbow", JointAction::Flexion), ("shoulder", JointAction::Extension)] {
+            assert_eq!(names(row(&matrix, joint, action), ResistanceProfile::Ascending), ["#4 row"]);
+        }
+    }
+
+    #[test]
+    fn exercises_sharing_an_id_are_listed_separately() {
+        let flexion = || vec![moving("elbow", JointAction::Flexion)];
+        let matrix = build(vec![
+            entry("curl.json", 2, flexion(), ResistanceProfile::Bell),
+            entry("hammer_curl.json", 2, flexion(), ResistanceProfile::Bell),
+        ]);
+        let flexion = row(&matrix, "elbow", JointAction::Flexion);
+        let cell = flexion.cell(&PlaneOfMotion::Sagittal, &ResistanceProfile::Bell).unwrap();
+        let paths: Vec<&Path> = cell.exercises.iter().map(|e| e.path.as_path()).collect();
+        assert_eq!(paths, [Path::new("curl.json"), Path::new("hammer_curl.json")]);
+    }
+}
@@ -1,31 +1,80 @@
-use crate::models::*;
+use hypertrophy_archive::analytics::E1rmFormula;
+use hypertrophy_archive::biomechanics::{CamPoint, LoadSource, Posture, TorqueModel};
+use hypertrophy_archive::coverage::CoverageMatrix;
+use hypertrophy_archive::library::{backup_file, read_exercise, sanitize_filename, write_exercise, Library};
+use hypertrophy_archive::models::*;
+use hypertrophy_archive::program::{load_programs, Program, Session, Slot, Week};
+use hypertrophy_archive::query::ExerciseQuery;
+use hypertrophy_archive::taxonomy::Taxonomy;
+use hypertrophy_archive::training_log::{new_session_path, LoggedExercise, LoggedSession, LoggedSet, TrainingLog};
+use hypertrophy_archive::validation::{Severity, ValidationReport};
+use hypertrophy_archive::volume::{Volume, VolumeConfig, VolumeStatus, WeekVolume};
+use crate::history::History;
+use chrono::Datelike;
 use eframe::egui;
-use egui::{Color32, RichText, Ui, InnerResponse};
-use std::collections::HashMap;
-use std::path::PathBuf;
-
-// Add this trait definition before the implementation
-trait UiExt {
-    fn horizontal_centered<R>(&mut self, add_contents: impl FnOnce(&mut Ui) -> R) -> InnerResponse<R>;
-}
-
-impl UiExt for Ui {
-    fn horizontal_centered<R>(&mut self, add_contents: impl FnOnce(&mut Ui) -> R) -> InnerResponse<R> {
-        self.with_layout(
-            egui::Layout::centered_and_justified(egui::Direction::LeftToRight),
-            add_contents
-        )
-    }
-}
+use egui::plot::{Bar, BarChart, Legend, Line, Plot, PlotPoint, PlotPoints, Points, VLine};
+use egui::{Color32, RichText, Ui};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 pub struct HypertrophyApp {
     exercise: Exercise,
+    history: History<Exercise>,
+    /// The exercise as it was last opened or saved, to tell whether there are unsaved changes.
+    saved_exercise: Exercise,
     status_message: String,
     data_dir: PathBuf,
-    muscle_divisions: HashMap<String, Vec<String>>,
-    joint_names: Vec<String>,
+    taxonomy: Taxonomy,
     current_tab: Tab,
     show_save_dialog: bool,
+    /// Filename typed into the save dialog, before sanitizing.
+    save_filename: String,
+    /// Folder the save dialog writes to.
+    save_dir: PathBuf,
+    library: Library,
+    selected_library_entry: Option<usize>,
+    library_query: ExerciseQuery,
+    source_path: Option<PathBuf>,
+    /// Modification time of `source_path` when it was opened or last saved.
+    source_modified: Option<SystemTime>,
+    keep_backups: bool,
+    confirm_discard: Option<DiscardAction>,
+    confirm_overwrite: Option<PathBuf>,
+    allowed_to_close: bool,
+    curve_compare_id: Option<u32>,
+    curve_probe_angle: u32,
+    curve_normalized: bool,
+    torque_model: TorqueModel,
+    torque_joint: usize,
+    programs_dir: PathBuf,
+    programs: Vec<(PathBuf, Program)>,
+    program: Program,
+    program_path: Option<PathBuf>,
+    volume_config: VolumeConfig,
+    /// Index of the program week shown in the volume table.
+    volume_week: usize,
+    coverage_joint: Option<String>,
+    coverage_used_only: bool,
+    /// Joint, action and cell index of the coverage cell whose exercises are listed.
+    coverage_selected: Option<(String, JointAction, usize)>,
+    training_log: TrainingLog,
+    /// The session being logged or edited.
+    log_session: LoggedSession,
+    log_path: Option<PathBuf>,
+    /// Date field of the session, kept as typed until it parses.
+    log_date: String,
+    log_confirm_delete: bool,
+    analytics_formula: E1rmFormula,
+    /// Exercise whose progress is charted, the first logged one if unset.
+    analytics_exercise: Option<u32>,
+    analytics_stall_sessions: usize,
+}
+
+/// Something that would throw away unsaved changes, held until the user confirms it.
+enum DiscardAction {
+    NewExercise,
+    Open(PathBuf, Box<Exercise>),
+    Close,
 }
 
 enum Tab {
@@ -34,119 +83,93 @@ enum Tab {
     JointsInvolved,
     AdditionalInfo,
     Preview,
+    Library,
+    Programs,
+    Coverage,
+    Log,
+    Analytics,
 }
 
 impl Default for HypertrophyApp {
     fn default() -> Self {
-        let exercise = Exercise {
-            name: String::new(),
-            id: 0,
-            r#type: ExerciseType::Compound,
-            description: String::new(),
-            target_muscles: TargetMuscles {
-                muscle_name: String::new(),
-                muscle_divisions: Vec::new(),
-            },
-            joints_involved: JointsInvolved { joints: Vec::new() },
-            resistance_profile: ResistanceProfile::Constant,
-            plane_of_motion: PlaneOfMotion::Transverse,
-            tips: String::new(),
-            technique_video: String::new(),
-        };
-
-        // Predefined muscle divisions
-        let mut muscle_divisions = HashMap::new();
-        muscle_divisions.insert(
-            "chest".to_string(),
-            vec![
-                "sternocostal".to_string(),
-                "clavicular".to_string(),
-                "abdominal".to_string(),
-            ],
-        );
-        muscle_divisions.insert(
-            "back".to_string(),
-            vec![
-                "upper trapezius".to_string(),
-                "middle trapezius".to_string(),
-                "lower trapezius".to_string(),
-                "rhomboids".to_string(),
-                "latissimus dorsi".to_string(),
-            ],
-        );
-        muscle_divisions.insert(
-            "shoulders".to_string(),
-            vec![
-                "anterior deltoid".to_string(),
-                "lateral deltoid".to_string(),
-                "posterior deltoid".to_string(),
-            ],
-        );
-        muscle_divisions.insert(
-            "biceps".to_string(),
-            vec![
-                "long head".to_string(),
-                "short head".to_string(),
-            ],
-        );
-        muscle_divisions.insert(
-            "triceps".to_string(),
-            vec![
-                "long head".to_string(),
-                "lateral head".to_string(),
-                "medial head".to_string(),
-            ],
-        );
-        muscle_divisions.insert(
-            "quadriceps".to_string(),
-            vec![
-                "rectus femoris".to_string(),
-                "vastus lateralis".to_string(),
-                "vastus medialis".to_string(),
-                "vastus intermedius".to_string(),
-            ],
-        );
-        muscle_divisions.insert(
-            "hamstrings".to_string(),
-            vec![
-                "biceps femoris".to_string(),
-                "semitendinosus".to_string(),
-                "semimembranosus".to_string(),
-            ],
-        );
+        let exercise = Exercise::default();
 
-        // Joint names
-        let joint_names = vec![
-            "shoulder".to_string(),
-            "elbow".to_string(),
-            "wrist".to_string(),
-            "hip".to_string(),
-            "knee".to_string(),
-            "ankle".to_string(),
-            "spine".to_string(),
-        ];
+        let data_dir = PathBuf::from("data");
+        let library = Library::load(&data_dir).unwrap_or_default();
+        let training_log = TrainingLog::load(&TrainingLog::dir_for_data_dir(&data_dir)).unwrap_or_default();
+        let today = chrono::Local::now().date_naive();
+        let volume_config = VolumeConfig::for_data_dir(&data_dir).unwrap_or_else(|_| VolumeConfig::builtin());
+        let programs_dir = Program::dir_for_data_dir(&data_dir);
+        let programs = load_programs(&programs_dir)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(path, program)| Some((path, program.ok()?)))
+            .collect();
+        let (taxonomy, status_message) = match Taxonomy::for_data_dir(&data_dir) {
+            Ok(taxonomy) => (taxonomy, "Ready to create a new exercise".to_string()),
+            Err(e) => (
+                Taxonomy::builtin(),
+                format!("Error reading {}: {}", Taxonomy::user_path(&data_dir).display(), e),
+            ),
+        };
 
         Self {
+            history: History::new(&exercise),
+            saved_exercise: exercise.clone(),
             exercise,
-            status_message: "Ready to create a new exercise".to_string(),
-            data_dir: PathBuf::from("data"),
-            muscle_divisions,
-            joint_names,
+            status_message,
+            save_dir: data_dir.clone(),
+            data_dir,
+            taxonomy,
             current_tab: Tab::BasicInfo,
             show_save_dialog: false,
+            save_filename: String::new(),
+            library,
+            selected_library_entry: None,
+            library_query: ExerciseQuery::default(),
+            source_path: None,
+            source_modified: None,
+            keep_backups: false,
+            confirm_discard: None,
+            confirm_overwrite: None,
+            allowed_to_close: false,
+            curve_compare_id: None,
+            curve_probe_angle: 90,
+            curve_normalized: false,
+            torque_model: TorqueModel::default(),
+            torque_joint: 0,
+            programs_dir,
+            programs,
+            program: Program::default(),
+            program_path: None,
+            volume_config,
+            volume_week: 0,
+            coverage_joint: None,
+            coverage_used_only: false,
+            coverage_selected: None,
+            training_log,
+            log_date: today.to_string(),
+            log_session: LoggedSession::new(today),
+            log_path: None,
+            log_confirm_delete: false,
+            analytics_formula: E1rmFormula::default(),
+            analytics_exercise: None,
+            analytics_stall_sessions: 3,
         }
     }
 }
 
 impl eframe::App for HypertrophyApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
         // Set global text scaling
         let mut style = (*ctx.style()).clone();
         style.text_styles.get_mut(&egui::TextStyle::Body).unwrap().size = 16.0;
         style.text_styles.get_mut(&egui::TextStyle::Button).unwrap().size = 18.0;
         style.text_styles.get_mut(&egui::TextStyle::Heading).unwrap().size = 24.0;
         ctx.set_style(style);
-        
+
+        self.handle_undo_shortcuts(ctx);
+
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.vertical_centered(|ui| {
                 ui.add_space(5.0);
@@ -154,27 +177,50 @@ impl eframe::App for HypertrophyApp {
                 ui.add_space(5.0);
             });
 
-            ui.add_space(-475.0);
+            ui.add_space(-452.0);
 
             ui.horizontal_centered(|ui| {
                 ui.add_space(20.0);
                 let button_size = egui::vec2(140.0, 40.0);
-                
-                if ui.add_sized(button_size, self.tab_button_styled("Basic Info", Tab::BasicInfo)).clicked() {
-                    self.current_tab = Tab::BasicInfo;
-                }
-                if ui.add_sized(button_size, self.tab_button_styled("Target Muscles", Tab::TargetMuscles)).clicked() {
-                    self.current_tab = Tab::TargetMuscles;
-                }
-                if ui.add_sized(button_size, self.tab_button_styled("Joints Involved", Tab::JointsInvolved)).clicked() {
-                    self.current_tab = Tab::JointsInvolved;
-                }
-                if ui.add_sized(button_size, self.tab_button_styled("Additional Info", Tab::AdditionalInfo)).clicked() {
-                    self.current_tab = Tab::AdditionalInfo;
-                }
-                if ui.add_sized(button_size, self.tab_button_styled("Preview", Tab::Preview)).clicked() {
-                    self.current_tab = Tab::Preview;
-                }
+
+                ui.vertical(|ui| {
+                    // Editing the current exercise
+                    ui.horizontal(|ui| {
+                        if ui.add_sized(button_size, self.tab_button_styled("Basic Info", Tab::BasicInfo)).clicked() {
+                            self.current_tab = Tab::BasicInfo;
+                        }
+                        if ui.add_sized(button_size, self.tab_button_styled("Target Muscles", Tab::TargetMuscles)).clicked() {
+                            self.current_tab = Tab::TargetMuscles;
+                        }
+                        if ui.add_sized(button_size, self.tab_button_styled("Joints Involved", Tab::JointsInvolved)).clicked() {
+                            self.current_tab = Tab::JointsInvolved;
+                        }
+                        if ui.add_sized(button_size, self.tab_button_styled("Additional Info", Tab::AdditionalInfo)).clicked() {
+                            self.current_tab = Tab::AdditionalInfo;
+                        }
+                        if ui.add_sized(button_size, self.tab_button_styled("Preview", Tab::Preview)).clicked() {
+                            self.current_tab = Tab::Preview;
+                        }
+                    });
+                    // Working with the archive as a whole
+                    ui.horizontal(|ui| {
+                        if ui.add_sized(button_size, self.tab_button_styled("Library", Tab::Library)).clicked() {
+                            self.current_tab = Tab::Library;
+                        }
+                        if ui.add_sized(button_size, self.tab_button_styled("Programs", Tab::Programs)).clicked() {
+                            self.current_tab = Tab::Programs;
+                        }
+                        if ui.add_sized(button_size, self.tab_button_styled("Coverage", Tab::Coverage)).clicked() {
+                            self.current_tab = Tab::Coverage;
+                        }
+                        if ui.add_sized(button_size, self.tab_button_styled("Training Log", Tab::Log)).clicked() {
+                            self.current_tab = Tab::Log;
+                        }
+                        if ui.add_sized(button_size, self.tab_button_styled("Analytics", Tab::Analytics)).clicked() {
+                            self.current_tab = Tab::Analytics;
+                        }
+                    });
+                });
                 ui.add_space(20.0);
             });
 
@@ -195,6 +241,11 @@ impl eframe::App for HypertrophyApp {
                                 Tab::JointsInvolved => self.show_joints_involved_tab(ui),
                                 Tab::AdditionalInfo => self.show_additional_info_tab(ui),
                                 Tab::Preview => self.show_preview_tab(ui),
+                                Tab::Library => self.show_library_tab(ui),
+                                Tab::Programs => self.show_programs_tab(ui),
+                                Tab::Coverage => self.show_coverage_tab(ui),
+                                Tab::Log => self.show_log_tab(ui),
+                                Tab::Analytics => self.show_analytics_tab(ui),
                             }
                         });
                 });
@@ -203,11 +254,30 @@ impl eframe::App for HypertrophyApp {
             ui.add_space(15.0);
             ui.separator();
             ui.horizontal(|ui| {
+                if self.is_dirty() {
+                    ui.label(RichText::new("Unsaved changes").size(16.0).color(Color32::from_rgb(230, 180, 80)));
+                }
                 ui.label(RichText::new(&self.status_message).size(16.0));
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::RIGHT), |ui| {
                     if ui.button(RichText::new("Save Exercise").size(18.0)).clicked() {
-                        self.show_save_dialog = true;
+                        self.request_save();
+                    }
+                    if ui.button(RichText::new("Open Exercise").size(18.0)).clicked() {
+                        self.open_exercise_dialog();
+                    }
+                    if ui.button(RichText::new("New Exercise").size(18.0)).clicked() {
+                        self.confirm_or_discard(DiscardAction::NewExercise);
                     }
+                    let can_redo = self.history.can_redo(&self.exercise);
+                    if ui.add_enabled(can_redo, egui::Button::new(RichText::new("Redo").size(18.0))).clicked() {
+                        self.redo();
+                    }
+                    let can_undo = self.history.can_undo(&self.exercise);
+                    if ui.add_enabled(can_undo, egui::Button::new(RichText::new("Undo").size(18.0))).clicked() {
+                        self.undo();
+                    }
+                    ui.checkbox(&mut self.keep_backups, "Keep .bak backups")
+                        .on_hover_text("Copy the previous version of a file to <name>.json.bak before overwriting it");
                 });
             });
 
@@ -215,7 +285,28 @@ impl eframe::App for HypertrophyApp {
             if self.show_save_dialog {
                 self.show_save_dialog(ctx);
             }
+            if self.confirm_overwrite.is_some() {
+                self.show_overwrite_dialog(ctx);
+            }
+            if self.confirm_discard.is_some() {
+                self.show_discard_dialog(ctx);
+            }
         });
+
+        if self.allowed_to_close {
+            frame.close();
+        }
+
+        let now = ctx.input(|i| i.time);
+        self.history.track(&self.exercise, now);
+    }
+
+    fn on_close_event(&mut self) -> bool {
+        if self.allowed_to_close || !self.is_dirty() {
+            return true;
+        }
+        self.confirm_discard = Some(DiscardAction::Close);
+        false
     }
 }
 
@@ -242,6 +333,18 @@ impl HypertrophyApp {
         ui.heading("Basic Information");
         ui.add_space(10.0);
 
+        ui.horizontal(|ui| {
+            ui.label("Exercise ID:");
+            if self.source_path.is_some() {
+                ui.label(format!("#{}", self.exercise.id));
+            } else {
+                match self.library.next_free_id() {
+                    Ok(id) => ui.label(RichText::new(format!("#{} (assigned on save)", id)).weak()),
+                    Err(e) => ui.colored_label(Color32::from_rgb(255, 120, 120), e.to_string()),
+                };
+            }
+        });
+
         ui.horizontal(|ui| {
             ui.label("Exercise Name:");
             ui.text_edit_singleline(&mut self.exercise.name);
@@ -277,28 +380,34 @@ impl HypertrophyApp {
             );
         });
 
+        let derived_profile = self.exercise.derived_resistance_profile();
         ui.horizontal(|ui| {
             ui.label("Resistance Profile:");
-            ui.radio_value(
-                &mut self.exercise.resistance_profile,
-                ResistanceProfile::Ascending,
-                "Ascending",
-            );
-            ui.radio_value(
-                &mut self.exercise.resistance_profile,
-                ResistanceProfile::Descending,
-                "Descending",
-            );
-            ui.radio_value(
-                &mut self.exercise.resistance_profile,
-                ResistanceProfile::Bell,
-                "Bell",
-            );
-            ui.radio_value(
-                &mut self.exercise.resistance_profile,
-                ResistanceProfile::Constant,
-                "Constant",
-            );
+            ui.add_enabled_ui(derived_profile.is_none(), |ui| {
+                ui.radio_value(
+                    &mut self.exercise.resistance_profile,
+                    ResistanceProfile::Ascending,
+                    "Ascending",
+                );
+                ui.radio_value(
+                    &mut self.exercise.resistance_profile,
+                    ResistanceProfile::Descending,
+                    "Descending",
+                );
+                ui.radio_value(
+                    &mut self.exercise.resistance_profile,
+                    ResistanceProfile::Bell,
+                    "Bell",
+                );
+                ui.radio_value(
+                    &mut self.exercise.resistance_profile,
+                    ResistanceProfile::Constant,
+                    "Constant",
+                );
+            });
+            if derived_profile.is_some() {
+                ui.label(RichText::new("(derived from the resistance curve)").weak());
+            }
         });
 
         ui.add_space(10.0);
@@ -311,56 +420,101 @@ impl HypertrophyApp {
         ui.heading("Target Muscles");
         ui.add_space(10.0);
 
-        ui.horizontal(|ui| {
-            ui.label("Primary Muscle:");
-            egui::ComboBox::from_label("")
-                .selected_text(RichText::new(&self.exercise.target_muscles.muscle_name).size(18.0))
-                .width(150.0)
-                .show_ui(ui, |ui| {
-                    for muscle in self.muscle_divisions.keys() {
-                        if ui
-                            .selectable_label(
-                                self.exercise.target_muscles.muscle_name == *muscle,
-                                muscle,
-                            )
-                            .clicked()
-                        {
-                            self.exercise.target_muscles.muscle_name = muscle.clone();
-                            self.exercise.target_muscles.muscle_divisions.clear();
+        let mut muscle_to_remove = None;
+        for (i, target) in self.exercise.target_muscles.muscles.iter_mut().enumerate() {
+            ui.group(|ui| {
+                ui.horizontal(|ui| {
+                    ui.label(format!("Muscle {}:", i + 1));
+                    egui::ComboBox::from_id_source(format!("muscle_name_combo_{}", i))
+                        .selected_text(RichText::new(&target.muscle_name).size(18.0))
+                        .width(150.0)
+                        .show_ui(ui, |ui| {
+                            for muscle in &self.taxonomy.muscles {
+                                if ui
+                                    .selectable_label(target.muscle_name == muscle.name, &muscle.name)
+                                    .clicked()
+                                    && target.muscle_name != muscle.name
+                                {
+                                    target.muscle_name = muscle.name.clone();
+                                    target.muscle_divisions.clear();
+                                }
+                            }
+                        });
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::RIGHT), |ui| {
+                        if ui.button("Remove").clicked() {
+                            muscle_to_remove = Some(i);
                         }
-                    }
+                    });
                 });
-        });
 
-        if !self.exercise.target_muscles.muscle_name.is_empty() {
-            ui.add_space(5.0);
-            ui.label("Muscle Divisions:");
-            
-            if let Some(divisions) = self.muscle_divisions.get(&self.exercise.target_muscles.muscle_name) {
-                for division in divisions {
-                    let mut is_active = self.exercise.target_muscles.muscle_divisions
-                        .iter()
-                        .any(|md| md.name == *division && md.active);
-                    
-                    if ui.checkbox(&mut is_active, division).changed() {
-                        self.exercise.target_muscles.muscle_divisions.retain(|md| md.name != *division);
-                        
-                        if is_active {
-                            self.exercise.target_muscles.muscle_divisions.push(MuscleDivision {
-                                name: division.clone(),
-                                active: true,
-                            });
-                        } else {
-                            self.exercise.target_muscles.muscle_divisions.push(MuscleDivision {
-                                name: division.clone(),
-                                active: false,
-                            });
+                ui.horizontal(|ui| {
+                    ui.label("Role:");
+                    ui.radio_value(&mut target.role, MuscleRole::Primary, "Primary");
+                    ui.radio_value(&mut target.role, MuscleRole::Secondary, "Secondary");
+                    ui.radio_value(&mut target.role, MuscleRole::Stabilizer, "Stabilizer");
+                });
+
+                if target.muscle_name.is_empty() {
+                    return;
+                }
+
+                ui.add_space(5.0);
+                ui.label("Division Emphasis:");
+                egui::Grid::new(format!("division_grid_{}", i)).show(ui, |ui| {
+                    for division in self.taxonomy.divisions(&target.muscle_name) {
+                        ui.label(division);
+                        let mut emphasis = target.emphasis(division);
+                        let before = emphasis;
+                        ui.radio_value(&mut emphasis, Emphasis::None, "None");
+                        ui.radio_value(&mut emphasis, Emphasis::Low, "Low");
+                        ui.radio_value(&mut emphasis, Emphasis::Moderate, "Moderate");
+                        ui.radio_value(&mut emphasis, Emphasis::High, "High");
+                        ui.end_row();
+
+                        if emphasis != before {
+                            target.muscle_divisions.retain(|md| md.name != *division);
+                            if emphasis != Emphasis::None {
+                                target.muscle_divisions.push(MuscleDivision {
+                                    name: division.clone(),
+                                    emphasis,
+                                });
+                            }
                         }
                     }
-                }
-            }
+                });
+            });
+            ui.add_space(5.0);
+        }
+
+        if let Some(index) = muscle_to_remove {
+            self.exercise.target_muscles.muscles.remove(index);
         }
 
+        if ui.button("Add Muscle").clicked() {
+            // The first muscle added is the primary one; later ones default to secondary
+            let role = if self.exercise.target_muscles.primary().is_none() {
+                MuscleRole::Primary
+            } else {
+                MuscleRole::Secondary
+            };
+            let name = self.taxonomy.muscles.first().map(|m| m.name.clone()).unwrap_or_default();
+            self.exercise.target_muscles.muscles.push(TargetMuscle::new(name, role));
+        }
+
+        ui.add_space(10.0);
+        ui.horizontal(|ui| {
+            ui.label(
+                RichText::new(format!(
+                    "Add muscles and divisions in {}",
+                    Taxonomy::user_path(&self.data_dir).display()
+                ))
+                .weak(),
+            );
+            if ui.small_button("Reload").clicked() {
+                self.reload_taxonomy();
+            }
+        });
+
         ui.add_space(10.0);
         ui.horizontal(|ui| {
             if ui.button("Previous: Basic Info").clicked() {
@@ -376,6 +530,7 @@ impl HypertrophyApp {
         ui.heading("Joints Involved");
         ui.add_space(10.0);
 
+        let derived_profile = self.exercise.derived_resistance_profile();
         let mut joints_to_remove = None;
         for (i, joint) in self.exercise.joints_involved.joints.iter_mut().enumerate() {
             ui.group(|ui| {
@@ -394,15 +549,19 @@ impl HypertrophyApp {
                         .selected_text(RichText::new(&joint.name).size(18.0))
                         .width(150.0)
                         .show_ui(ui, |ui| {
-                            for joint_name in &self.joint_names {
-                                if ui.selectable_label(joint.name == *joint_name, joint_name).clicked() {
-                                    joint.name = joint_name.clone();
+                            for spec in &self.taxonomy.joints {
+                                if ui.selectable_label(joint.name == spec.name, &spec.name).clicked() {
+                                    joint.name = spec.name.clone();
+                                    // Drop an action the new joint cannot perform
+                                    if joint.direction.is_some_and(|action| !spec.allows(action)) {
+                                        joint.direction = None;
+                                    }
                                 }
                             }
                         });
                 });
 
-                ui.checkbox(&mut joint.dynamic, "Dynamic Movement").changed().then(|| {
+                if ui.checkbox(&mut joint.dynamic, "Dynamic Movement").changed() {
                     if joint.dynamic {
                         joint.angle = None;
                         if joint.angle_initial.is_none() {
@@ -413,22 +572,37 @@ impl HypertrophyApp {
                         }
                     } else {
                         joint.direction = None;
+                        joint.unmapped_direction = None;
                         joint.angle_initial = None;
                         joint.angle_final = None;
+                        joint.resistance_curve = None;
                         if joint.angle.is_none() {
                             joint.angle = Some(0);
                         }
                     }
-                });
+                }
 
                 if joint.dynamic {
                     ui.horizontal(|ui| {
                         ui.label("Direction:");
-                        
-                        let mut direction_text = joint.direction.clone().unwrap_or_default();
-                        
-                        if ui.text_edit_singleline(&mut direction_text).changed() {
-                            joint.direction = Some(direction_text);
+                        let actions = self
+                            .taxonomy
+                            .joint(&joint.name)
+                            .map_or(&JointAction::ALL[..], |spec| spec.allowed_actions());
+                        egui::ComboBox::from_id_source(format!("joint_direction_combo_{}", i))
+                            .selected_text(joint.direction.map_or("Select...", |a| a.label()))
+                            .width(180.0)
+                            .show_ui(ui, |ui| {
+                                for action in actions {
+                                    ui.selectable_value(&mut joint.direction, Some(*action), action.label());
+                                }
+                            });
+                        if joint.direction.is_some() {
+                            joint.unmapped_direction = None;
+                        } else if let Some(text) = &joint.unmapped_direction {
+                            ui.label(
+                                RichText::new(format!("was \"{}\"", text)).color(Color32::from_rgb(255, 200, 100)),
+                            );
                         }
                     });
                     
@@ -447,6 +621,11 @@ impl HypertrophyApp {
                             joint.angle_final = Some(angle);
                         }
                     });
+
+                    egui::CollapsingHeader::new("Resistance Curve")
+                        .id_source(format!("resistance_curve_{}", i))
+                        .default_open(joint.resistance_curve.is_some())
+                        .show(ui, |ui| show_curve_editor(ui, i, joint));
                 } else {
                     ui.horizontal(|ui| {
                         ui.label("Fixed Angle:");
@@ -463,23 +642,35 @@ impl HypertrophyApp {
         if let Some(index) = joints_to_remove {
             self.exercise.joints_involved.joints.remove(index);
         }
+        // Only follow the curve when this frame's edits changed what it implies,
+        // so an untouched exercise is not rewritten on every repaint
+        if self.exercise.derived_resistance_profile() != derived_profile {
+            self.exercise.sync_resistance_profile();
+        }
 
         // Add new joint button
         if ui.button("Add Joint").clicked() {
             let joint_id = self.exercise.joints_involved.joints.len();
             
             self.exercise.joints_involved.joints.push(Joint {
-                name: self.joint_names.first().cloned().unwrap_or_default(),
+                name: self.taxonomy.joints.first().map(|j| j.name.clone()).unwrap_or_default(),
                 dynamic: false,
                 angle: Some(0),
                 direction: None,
+                unmapped_direction: None,
                 angle_initial: None,
                 angle_final: None,
+                resistance_curve: None,
             });
             
             self.status_message = format!("Added new joint (#{}).", joint_id + 1);
         }
 
+        ui.add_space(10.0);
+        self.show_torque_calculator(ui);
+        ui.add_space(10.0);
+        self.show_curve_comparison(ui);
+
         ui.add_space(10.0);
         ui.horizontal(|ui| {
             if ui.button("Previous: Target Muscles").clicked() {
@@ -504,6 +695,53 @@ impl HypertrophyApp {
             ui.text_edit_singleline(&mut self.exercise.technique_video);
         });
 
+        ui.add_space(10.0);
+        ui.label(RichText::new("Equipment Variants").strong());
+        ui.label(RichText::new("Concrete implementations of this exercise and the profile each produces.").weak());
+        let mut variant_to_remove = None;
+        for (i, variant) in self.exercise.variants.iter_mut().enumerate() {
+            ui.group(|ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Equipment:");
+                    egui::ComboBox::from_id_source(format!("variant_equipment_combo_{}", i))
+                        .selected_text(variant.equipment.label())
+                        .width(120.0)
+                        .show_ui(ui, |ui| {
+                            for equipment in Equipment::ALL {
+                                ui.selectable_value(&mut variant.equipment, equipment, equipment.label());
+                            }
+                        });
+                    ui.label("Name:");
+                    ui.text_edit_singleline(&mut variant.name);
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::RIGHT), |ui| {
+                        if ui.button("Remove").clicked() {
+                            variant_to_remove = Some(i);
+                        }
+                    });
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Resistance Profile:");
+                    ui.radio_value(&mut variant.resistance_profile, ResistanceProfile::Ascending, "Ascending");
+                    ui.radio_value(&mut variant.resistance_profile, ResistanceProfile::Descending, "Descending");
+                    ui.radio_value(&mut variant.resistance_profile, ResistanceProfile::Bell, "Bell");
+                    ui.radio_value(&mut variant.resistance_profile, ResistanceProfile::Constant, "Constant");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Notes:");
+                    ui.text_edit_singleline(&mut variant.notes);
+                });
+            });
+        }
+        if let Some(index) = variant_to_remove {
+            self.exercise.variants.remove(index);
+        }
+        if ui.button("Add Variant").clicked() {
+            self.exercise.variants.push(EquipmentVariant {
+                resistance_profile: self.exercise.resistance_profile.clone(),
+                ..Default::default()
+            });
+        }
+
         ui.add_space(10.0);
         ui.horizontal(|ui| {
             if ui.button("Previous: Joints Involved").clicked() {
@@ -521,46 +759,16 @@ impl HypertrophyApp {
 
         let json = serde_json::to_string_pretty(&self.exercise).unwrap_or_else(|_| "Error serializing exercise".to_string());
         
-        ui.group(|ui| {
-            ui.label(format!("Name: {}", self.exercise.name));
-            ui.label(format!("Type: {:?}", self.exercise.r#type));
-            ui.label(format!("Description: {}", self.exercise.description));
-            ui.label(format!("Plane of Motion: {:?}", self.exercise.plane_of_motion));
-            ui.label(format!("Resistance Profile: {:?}", self.exercise.resistance_profile));
-            
-            ui.add_space(5.0);
-            ui.label(format!("Target Muscle: {}", self.exercise.target_muscles.muscle_name));
-            ui.label("Active Divisions:");
-            for division in &self.exercise.target_muscles.muscle_divisions {
-                if division.active {
-                    ui.label(format!("- {}", division.name));
-                }
-            }
-            
-            ui.add_space(5.0);
-            ui.label("Joints Involved:");
-            for joint in &self.exercise.joints_involved.joints {
-                if joint.dynamic {
-                    ui.label(format!(
-                        "- {} (Dynamic): {} from {}° to {}°",
-                        joint.name,
-                        joint.direction.clone().unwrap_or_default(),
-                        joint.angle_initial.unwrap_or(0),
-                        joint.angle_final.unwrap_or(0)
-                    ));
-                } else {
-                    ui.label(format!(
-                        "- {} (Static): {}°",
-                        joint.name,
-                        joint.angle.unwrap_or(0)
-                    ));
-                }
-            }
-            
-            ui.add_space(5.0);
-            ui.label(format!("Tips: {}", self.exercise.tips));
-            ui.label(format!("Video: {}", self.exercise.technique_video));
-        });
+        show_exercise_summary(ui, &self.exercise);
+
+        ui.add_space(5.0);
+        show_stretch_scores(ui, &self.exercise, &self.taxonomy);
+
+        ui.add_space(5.0);
+        self.show_similar_exercises(ui);
+
+        ui.add_space(5.0);
+        show_validation_report(ui, &self.exercise.validate_with(&self.taxonomy));
 
         ui.collapsing("JSON Preview", |ui| {
             ui.monospace(json);
@@ -572,66 +780,1939 @@ impl HypertrophyApp {
                 self.current_tab = Tab::AdditionalInfo;
             }
             if ui.button("Save Exercise").clicked() {
-                self.show_save_dialog = true;
+                self.request_save();
             }
         });
     }
 
-    fn show_save_dialog(&mut self, ctx: &egui::Context) {
-        egui::Window::new("Save Exercise")
-            .fixed_size([300.0, 100.0])
-            .show(ctx, |ui| {
-                ui.label("Enter a filename to save the exercise:");
-                
-                let mut filename = self.exercise.name.clone();
-                ui.text_edit_singleline(&mut filename);
-                
-                ui.horizontal(|ui| {
-                    if ui.button("Cancel").clicked() {
-                        self.show_save_dialog = false;
-                    }
-                    
-                    if ui.button("Save").clicked() {
-                        self.save_exercise(&filename);
-                        self.show_save_dialog = false;
+    /// The library exercises closest to the one being edited, as substitutes.
+    fn show_similar_exercises(&mut self, ui: &mut Ui) {
+        const SHOWN: usize = 5;
+        let mut open_entry = None;
+        ui.collapsing("Similar Exercises", |ui| {
+            let substitutes = self.library.substitutes(&self.exercise, self.source_path.as_deref());
+            if substitutes.is_empty() {
+                ui.label("The library has no other exercises to compare with.");
+                return;
+            }
+            egui::Grid::new("similar_exercises_grid").num_columns(3).show(ui, |ui| {
+                for substitute in substitutes.iter().take(SHOWN) {
+                    let s = substitute.similarity;
+                    ui.label(RichText::new(format!("{:.0}%", s.score * 100.0)).strong()).on_hover_text(format!(
+                        "Muscles {:.0}%, joints {:.0}%, range {:.0}%, profile {:.0}%, plane {:.0}%",
+                        s.muscles * 100.0,
+                        s.joints * 100.0,
+                        s.range * 100.0,
+                        s.profile * 100.0,
+                        s.plane * 100.0
+                    ));
+                    let exercise = &substitute.entry.exercise;
+                    ui.label(format!(
+                        "#{} {} ({:?}, {:?})",
+                        exercise.id, exercise.name, exercise.resistance_profile, exercise.plane_of_motion
+                    ));
+                    if ui.button("Open in Editor").clicked() {
+                        open_entry = Some((substitute.entry.path.clone(), exercise.clone()));
                     }
-                });
+                    ui.end_row();
+                }
             });
+        });
+        if let Some((path, exercise)) = open_entry {
+            self.confirm_or_discard(DiscardAction::Open(path, Box::new(exercise)));
+        }
     }
 
-    fn save_exercise(&mut self, filename: &str) {
-        use std::fs::{self, File};
-        use std::io::Write;
-        
-        let sanitized = filename.trim().replace(" ", "_").to_lowercase();
-        let filename = if sanitized.is_empty() { "unnamed_exercise".to_string() } else { sanitized };
-        
-        // Ensure data directory exists
-        if !self.data_dir.exists() {
-            if let Err(e) = fs::create_dir_all(&self.data_dir) {
-                self.status_message = format!("Error creating data directory: {}", e);
-                return;
+    fn show_library_tab(&mut self, ui: &mut Ui) {
+        ui.heading("Exercise Library");
+        ui.add_space(10.0);
+
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "{} exercises in {}",
+                self.library.len(),
+                self.data_dir.display()
+            ));
+            if ui.button("Refresh").clicked() {
+                self.reload_library();
             }
-        }
-        
-        // Create file path
-        let file_path = self.data_dir.join(format!("{}.json", filename));
-        
-        match serde_json::to_string_pretty(&self.exercise) {
-            Ok(json) => {
-                // Write to file
-                match File::create(&file_path).and_then(|mut file| file.write_all(json.as_bytes())) {
-                    Ok(_) => {
-                        self.status_message = format!("Exercise saved to {}", file_path.display());
-                    }
-                    Err(e) => {
-                        self.status_message = format!("Error writing file: {}", e);
+        });
+
+        if !self.library.failures.is_empty() {
+            ui.add_space(5.0);
+            ui.collapsing(
+                RichText::new(format!("{} file(s) failed to load", self.library.failures.len()))
+                    .color(Color32::from_rgb(255, 120, 120)),
+                |ui| {
+                    for failure in &self.library.failures {
+                        ui.label(format!("{}: {}", failure.path.display(), failure.error));
                     }
+                },
+            );
+        }
+
+        let duplicates = self.library.duplicate_ids();
+        if !duplicates.is_empty() {
+            ui.add_space(5.0);
+            let mut renumber = false;
+            ui.group(|ui| {
+                ui.label(
+                    RichText::new(format!("{} id(s) are used by more than one file", duplicates.len()))
+                        .color(Color32::from_rgb(255, 200, 100)),
+                );
+                for (id, paths) in &duplicates {
+                    let files: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+                    ui.label(format!("#{}: {}", id, files.join(", ")));
+                }
+                renumber = ui.button("Renumber duplicates").clicked();
+            });
+            if renumber {
+                self.renumber_duplicates();
+            }
+        }
+
+        ui.add_space(10.0);
+        if self.library.is_empty() {
+            ui.label("No exercises found. Saved exercises will show up here.");
+            return;
+        }
+
+        self.show_library_filters(ui);
+        ui.add_space(10.0);
+
+        let mut open_entry = None;
+        let query = &self.library_query;
+        ui.columns(2, |columns| {
+            let mut shown = 0;
+            for (i, entry) in self.library.entries.iter().enumerate() {
+                if !query.matches(&entry.exercise, &self.taxonomy) {
+                    continue;
+                }
+                shown += 1;
+                let label = format!(
+                    "#{} {} ({})",
+                    entry.exercise.id,
+                    entry.exercise.name,
+                    entry.exercise.target_muscles.primary_name()
+                );
+                if columns[0]
+                    .selectable_label(self.selected_library_entry == Some(i), label)
+                    .clicked()
+                {
+                    self.selected_library_entry = Some(i);
                 }
             }
-            Err(e) => {
-                self.status_message = format!("Error serializing exercise: {}", e);
+            if shown == 0 {
+                columns[0].label("No exercises match the filters.");
+            }
+
+            match self.selected_library_entry.and_then(|i| self.library.entries.get(i)) {
+                Some(entry) => {
+                    columns[1].label(RichText::new(entry.path.display().to_string()).weak());
+                    if columns[1].button("Open in Editor").clicked() {
+                        open_entry = Some((entry.path.clone(), entry.exercise.clone()));
+                    }
+                    show_exercise_summary(&mut columns[1], &entry.exercise);
+                }
+                None => {
+                    columns[1].label("Select an exercise to see its details.");
+                }
             }
+        });
+
+        if let Some((path, exercise)) = open_entry {
+            self.confirm_or_discard(DiscardAction::Open(path, Box::new(exercise)));
         }
     }
-} 
\ No newline at end of file
+
+    fn show_log_tab(&mut self, ui: &mut Ui) {
+        ui.heading("Training Log");
+        ui.add_space(10.0);
+
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "{} sessions in {}",
+                self.training_log.len(),
+                TrainingLog::dir_for_data_dir(&self.data_dir).display()
+            ));
+            if ui.button("Refresh").clicked() {
+                self.reload_training_log();
+            }
+        });
+        if !self.training_log.failures.is_empty() {
+            ui.collapsing(
+                RichText::new(format!("{} file(s) failed to load", self.training_log.failures.len()))
+                    .color(Color32::from_rgb(255, 120, 120)),
+                |ui| {
+                    for failure in &self.training_log.failures {
+                        ui.label(format!("{}: {}", failure.path.display(), failure.error));
+                    }
+                },
+            );
+        }
+
+        let mut open_session = None;
+        let mut start_from = None;
+        ui.horizontal(|ui| {
+            ui.label("Open:");
+            let selected = self
+                .log_path
+                .as_ref()
+                .and_then(|path| path.file_stem())
+                .map_or("Unsaved session".to_string(), |stem| stem.to_string_lossy().to_string());
+            egui::ComboBox::from_id_source("log_session_combo")
+                .selected_text(selected)
+                .width(200.0)
+                .show_ui(ui, |ui| {
+                    for (i, entry) in self.training_log.entries.iter().enumerate().rev() {
+                        let is_open = self.log_path.as_ref() == Some(&entry.path);
+                        let label = format!("{} {}", entry.session.date, entry.session.name);
+                        if ui.selectable_label(is_open, label).clicked() {
+                            open_session = Some(i);
+                        }
+                    }
+                });
+            if ui.button("New Session").clicked() {
+                self.start_log_session(LoggedSession::new(chrono::Local::now().date_naive()));
+            }
+            egui::ComboBox::from_id_source("log_program_combo")
+                .selected_text("Start from program...")
+                .width(220.0)
+                .show_ui(ui, |ui| {
+                    for (_, program) in &self.programs {
+                        for (w, week) in program.weeks.iter().enumerate() {
+                            for session in &week.sessions {
+                                let label = format!("{}, week {}, {}", program.name, w + 1, session.name);
+                                if ui.selectable_label(false, label).clicked() {
+                                    start_from = Some(session.clone());
+                                }
+                            }
+                        }
+                    }
+                });
+        });
+        if let Some(i) = open_session {
+            let entry = &self.training_log.entries[i];
+            self.status_message = format!("Opened {}", entry.path.display());
+            self.log_path = Some(entry.path.clone());
+            self.log_session = entry.session.clone();
+            self.log_date = self.log_session.date.to_string();
+        }
+        if let Some(session) = start_from {
+            let today = chrono::Local::now().date_naive();
+            self.start_log_session(LoggedSession::from_program(today, &session));
+        }
+
+        ui.add_space(10.0);
+        ui.horizontal(|ui| {
+            ui.label("Date:");
+            if ui.text_edit_singleline(&mut self.log_date).changed()
+                && let Ok(date) = self.log_date.trim().parse()
+            {
+                self.log_session.date = date;
+            }
+            if self.log_date.trim().parse::<chrono::NaiveDate>().is_err() {
+                ui.colored_label(Color32::from_rgb(255, 120, 120), "Use YYYY-MM-DD");
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Session Name:");
+            ui.text_edit_singleline(&mut self.log_session.name);
+        });
+        ui.horizontal(|ui| {
+            ui.label("Notes:");
+            ui.text_edit_multiline(&mut self.log_session.notes);
+        });
+
+        let unknown = self.log_session.unknown_exercise_ids(&self.library);
+        if !unknown.is_empty() {
+            let ids: Vec<String> = unknown.iter().map(|id| format!("#{}", id)).collect();
+            ui.colored_label(
+                Color32::from_rgb(230, 180, 80),
+                format!("Not in the library: {}", ids.join(", ")),
+            );
+        }
+
+        let library = &self.library;
+        let log = &self.training_log;
+        let session = &mut self.log_session;
+        let mut exercise_to_remove = None;
+        for (i, logged) in session.exercises.iter_mut().enumerate() {
+            ui.group(|ui| {
+                ui.horizontal(|ui| {
+                    let name = library
+                        .find_by_id(logged.exercise_id)
+                        .map_or("(missing)", |entry| entry.exercise.name.as_str());
+                    ui.label(RichText::new(format!("#{} {}", logged.exercise_id, name)).strong());
+                    if ui.button("Remove Exercise").clicked() {
+                        exercise_to_remove = Some(i);
+                    }
+                });
+                let previous = log
+                    .entries
+                    .iter()
+                    .rev()
+                    .filter(|entry| entry.session.date < session.date)
+                    .find_map(|entry| entry.session.exercises.iter().find(|e| e.exercise_id == logged.exercise_id));
+                if let Some(previous) = previous {
+                    let sets: Vec<String> = previous.sets.iter().map(|s| format!("{}x{}", s.load, s.reps)).collect();
+                    ui.label(RichText::new(format!("Last time: {}", sets.join(", "))).weak());
+                }
+                show_logged_sets(ui, i, logged);
+                ui.horizontal(|ui| {
+                    ui.label("Notes:");
+                    ui.text_edit_singleline(&mut logged.notes);
+                });
+            });
+        }
+        if let Some(index) = exercise_to_remove {
+            session.exercises.remove(index);
+        }
+        egui::ComboBox::from_id_source("log_add_exercise")
+            .selected_text("Add exercise...")
+            .width(260.0)
+            .show_ui(ui, |ui| {
+                for entry in &library.entries {
+                    let label = format!("#{} {}", entry.exercise.id, entry.exercise.name);
+                    if ui.selectable_label(false, label).clicked() {
+                        session.exercises.push(LoggedExercise {
+                            exercise_id: entry.exercise.id,
+                            sets: vec![LoggedSet { reps: 10, load: 0.0, rir: None }],
+                            notes: String::new(),
+                        });
+                    }
+                }
+            });
+
+        ui.add_space(10.0);
+        ui.horizontal(|ui| {
+            if ui.button("Save Session").clicked() {
+                self.save_log_session();
+            }
+            if self.log_path.is_some() {
+                if self.log_confirm_delete {
+                    ui.label("Delete this session from the log?");
+                    if ui.button("Delete").clicked() {
+                        self.delete_log_session();
+                    }
+                    if ui.button("Keep").clicked() {
+                        self.log_confirm_delete = false;
+                    }
+                } else if ui.button("Delete Session").clicked() {
+                    self.log_confirm_delete = true;
+                }
+            }
+        });
+    }
+
+    fn show_analytics_tab(&mut self, ui: &mut Ui) {
+        ui.heading("Analytics");
+        ui.add_space(10.0);
+
+        ui.horizontal(|ui| {
+            ui.label(format!("{} logged sessions", self.training_log.len()));
+            if ui.button("Refresh").clicked() {
+                self.reload_training_log();
+            }
+            ui.add_space(20.0);
+            ui.label("e1RM formula:");
+            egui::ComboBox::from_id_source("analytics_formula_combo")
+                .selected_text(self.analytics_formula.label())
+                .show_ui(ui, |ui| {
+                    for formula in E1rmFormula::ALL {
+                        ui.selectable_value(&mut self.analytics_formula, formula, formula.label());
+                    }
+                });
+        });
+
+        let progress = self.training_log.progress(self.analytics_formula);
+        if progress.is_empty() {
+            ui.label("Log some sessions in the Training Log tab to see their progress here.");
+            return;
+        }
+        let library = &self.library;
+        let name = |id: u32| library.find_by_id(id).map_or("(missing)".to_string(), |e| e.exercise.name.clone());
+        let selected = progress
+            .iter()
+            .find(|p| Some(p.exercise_id) == self.analytics_exercise)
+            .unwrap_or(&progress[0]);
+
+        ui.add_space(10.0);
+        ui.horizontal(|ui| {
+            ui.label("Exercise:");
+            egui::ComboBox::from_id_source("analytics_exercise_combo")
+                .selected_text(format!("#{} {}", selected.exercise_id, name(selected.exercise_id)))
+                .width(260.0)
+                .show_ui(ui, |ui| {
+                    for p in &progress {
+                        let label = format!("#{} {} ({} days)", p.exercise_id, name(p.exercise_id), p.points.len());
+                        if ui.selectable_label(p.exercise_id == selected.exercise_id, label).clicked() {
+                            self.analytics_exercise = Some(p.exercise_id);
+                        }
+                    }
+                });
+        });
+        ui.label(
+            RichText::new("e1RM is the best estimate from the day's sets of up to 12 reps. Tonnage is reps times load over all sets.")
+                .weak(),
+        );
+
+        let e1rm: Vec<[f64; 2]> = selected
+            .points
+            .iter()
+            .filter_map(|p| Some([day_number(p.date), p.e1rm?]))
+            .collect();
+        let tonnage: Vec<[f64; 2]> = selected.points.iter().map(|p| [day_number(p.date), p.tonnage]).collect();
+        ui.columns(2, |columns| {
+            let charts = [
+                ("analytics_e1rm_plot", "e1RM", e1rm, Color32::from_rgb(120, 200, 255)),
+                ("analytics_tonnage_plot", "Tonnage", tonnage, Color32::from_rgb(150, 220, 150)),
+            ];
+            for (ui, (id, title, points, color)) in columns.iter_mut().zip(charts) {
+                ui.label(RichText::new(title).strong());
+                Plot::new(id)
+                    .height(200.0)
+                    .include_y(0.0)
+                    .x_axis_formatter(|x, _| day_label(x))
+                    .label_formatter(|name, value: &PlotPoint| format!("{}\n{} {:.1}", day_label(value.x.round()), name, value.y))
+                    .show(ui, |plot_ui| {
+                        plot_ui.line(Line::new(PlotPoints::new(points.clone())).color(color).name(title));
+                        plot_ui.points(Points::new(PlotPoints::new(points)).radius(3.0).color(color).name(title));
+                    });
+            }
+        });
+
+        ui.add_space(10.0);
+        ui.horizontal(|ui| {
+            ui.label(RichText::new("Stalled").strong());
+            ui.label("without a new best e1RM in the last");
+            ui.add(egui::DragValue::new(&mut self.analytics_stall_sessions).clamp_range(1..=20));
+            ui.label("sessions");
+        });
+        let stalls: Vec<_> = progress.iter().filter_map(|p| p.stall(self.analytics_stall_sessions)).collect();
+        if stalls.is_empty() {
+            ui.label("No stalled exercises.");
+        }
+        for stall in &stalls {
+            ui.colored_label(
+                Color32::from_rgb(230, 180, 80),
+                format!(
+                    "#{} {}: best {:.1} on {}, {} sessions since",
+                    stall.exercise_id,
+                    name(stall.exercise_id),
+                    stall.best,
+                    stall.best_date,
+                    stall.sessions
+                ),
+            );
+        }
+
+        ui.add_space(10.0);
+        ui.label(RichText::new("Weekly hard sets per muscle").strong());
+        let weeks = self.training_log.weekly_muscle_volume(&self.library, &self.volume_config);
+        let mut muscles: Vec<&String> = weeks.values().flat_map(|m| m.keys()).collect();
+        muscles.sort();
+        muscles.dedup();
+        Plot::new("analytics_volume_plot")
+            .height(220.0)
+            .legend(Legend::default())
+            .include_y(0.0)
+            .x_axis_formatter(|x, _| day_label(x))
+            .label_formatter(|name, value: &PlotPoint| {
+                format!("Week of {}\n{} {:.1} sets", day_label(value.x.round()), name, value.y)
+            })
+            .show(ui, |plot_ui| {
+                for muscle in muscles {
+                    // Weeks without the muscle count as zero sets
+                    let points: Vec<[f64; 2]> = weeks
+                        .iter()
+                        .map(|(week_start, sets)| [day_number(*week_start), sets.get(muscle).copied().unwrap_or(0.0)])
+                        .collect();
+                    plot_ui.line(Line::new(PlotPoints::new(points)).name(muscle));
+                }
+            });
+    }
+
+    fn start_log_session(&mut self, session: LoggedSession) {
+        self.log_date = session.date.to_string();
+        self.log_session = session;
+        self.log_path = None;
+        self.log_confirm_delete = false;
+        self.status_message = "Ready to log a new session".to_string();
+    }
+
+    fn reload_training_log(&mut self) {
+        match TrainingLog::load(&TrainingLog::dir_for_data_dir(&self.data_dir)) {
+            Ok(log) => {
+                self.status_message = format!("Loaded {} logged sessions", log.len());
+                self.training_log = log;
+            }
+            Err(e) => {
+                self.status_message = format!("Error reading training log: {}", e);
+            }
+        }
+    }
+
+    /// Saves back to the session's file, or to a new file named after its date.
+    fn save_log_session(&mut self) {
+        if self.log_date.trim().parse::<chrono::NaiveDate>().is_err() {
+            self.status_message = format!("Cannot save: '{}' is not a date (YYYY-MM-DD)", self.log_date);
+            return;
+        }
+        let dir = TrainingLog::dir_for_data_dir(&self.data_dir);
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            self.status_message = format!("Error creating {}: {}", dir.display(), e);
+            return;
+        }
+        let path = self
+            .log_path
+            .clone()
+            .unwrap_or_else(|| new_session_path(&dir, self.log_session.date));
+
+        match self.log_session.save(&path) {
+            Ok(()) => {
+                self.reload_training_log();
+                self.status_message = format!("Session logged to {}", path.display());
+                self.log_path = Some(path);
+            }
+            Err(e) => {
+                self.status_message = format!("Error saving session: {}", e);
+            }
+        }
+    }
+
+    fn delete_log_session(&mut self) {
+        self.log_confirm_delete = false;
+        let Some(path) = self.log_path.clone() else {
+            return;
+        };
+        match std::fs::remove_file(&path) {
+            Ok(()) => {
+                self.reload_training_log();
+                self.start_log_session(LoggedSession::new(self.log_session.date));
+                self.status_message = format!("Deleted {}", path.display());
+            }
+            Err(e) => {
+                self.status_message = format!("Error deleting {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    fn show_coverage_tab(&mut self, ui: &mut Ui) {
+        ui.heading("Coverage");
+        ui.add_space(10.0);
+
+        let mut matrix = CoverageMatrix::build(&self.library, &self.taxonomy);
+        ui.label(format!(
+            "{} of {} combinations of joint action, resistance profile and plane have an exercise.",
+            matrix.filled(),
+            matrix.cell_count()
+        ));
+
+        ui.horizontal(|ui| {
+            ui.label("Joint:");
+            let mut joints: Vec<String> = matrix.rows.iter().map(|r| r.joint.clone()).collect();
+            joints.dedup();
+            egui::ComboBox::from_id_source("coverage_joint_combo")
+                .selected_text(self.coverage_joint.as_deref().unwrap_or("All"))
+                .width(160.0)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.coverage_joint, None, "All");
+                    for joint in joints {
+                        ui.selectable_value(&mut self.coverage_joint, Some(joint.clone()), joint);
+                    }
+                });
+            ui.checkbox(&mut self.coverage_used_only, "Only actions with exercises");
+        });
+        matrix.rows.retain(|row| {
+            self.coverage_joint.as_ref().is_none_or(|joint| *joint == row.joint)
+                && (!self.coverage_used_only || row.filled() > 0)
+        });
+        ui.add_space(10.0);
+
+        let empty_fill = Color32::from_rgb(90, 45, 45);
+        let filled_fill = Color32::from_rgb(40, 90, 55);
+        egui::Grid::new("coverage_grid").spacing([4.0, 4.0]).show(ui, |ui| {
+            ui.label("");
+            for plane in PlaneOfMotion::ALL {
+                ui.label(RichText::new(plane.label()).strong());
+                for _ in 1..ResistanceProfile::ALL.len() {
+                    ui.label("");
+                }
+            }
+            ui.end_row();
+            ui.label(RichText::new("Joint Action").strong());
+            for _ in PlaneOfMotion::ALL {
+                for profile in ResistanceProfile::ALL {
+                    ui.label(profile.label());
+                }
+            }
+            ui.end_row();
+
+            for row in &matrix.rows {
+                ui.label(row.label());
+                for (i, cell) in row.cells.iter().enumerate() {
+                    let selected = self
+                        .coverage_selected
+                        .as_ref()
+                        .is_some_and(|(joint, action, index)| *joint == row.joint && *action == row.action && *index == i);
+                    let text = if cell.is_empty() { "-".to_string() } else { cell.exercises.len().to_string() };
+                    let button = egui::Button::new(text)
+                        .fill(if cell.is_empty() { empty_fill } else { filled_fill })
+                        .stroke(if selected {
+                            egui::Stroke::new(2.0, Color32::from_rgb(120, 200, 255))
+                        } else {
+                            egui::Stroke::NONE
+                        });
+                    let hover = if cell.is_empty() {
+                        format!("No {} exercise in the {} plane yet", cell.profile.label(), cell.plane.label())
+                    } else {
+                        cell.exercises.iter().map(|e| e.label()).collect::<Vec<_>>().join("\n")
+                    };
+                    if ui.add_sized([70.0, 24.0], button).on_hover_text(hover).clicked() {
+                        self.coverage_selected = Some((row.joint.clone(), row.action, i));
+                    }
+                }
+                ui.end_row();
+            }
+        });
+
+        let selected = self.coverage_selected.as_ref().and_then(|(joint, action, index)| {
+            let row = matrix.rows.iter().find(|r| r.joint == *joint && r.action == *action)?;
+            Some((row, row.cells.get(*index)?))
+        });
+        let mut open_entry = None;
+        if let Some((row, cell)) = selected {
+            ui.add_space(10.0);
+            ui.label(
+                RichText::new(format!(
+                    "{}, {} profile, {} plane",
+                    row.label(),
+                    cell.profile.label(),
+                    cell.plane.label()
+                ))
+                .strong(),
+            );
+            if cell.is_empty() {
+                ui.label("No exercise covers this combination yet.");
+            }
+            for exercise in &cell.exercises {
+                ui.horizontal(|ui| {
+                    ui.label(exercise.label());
+                    if let Some(entry) = self.library.entries.iter().find(|e| e.path == exercise.path)
+                        && ui.button("Open in Editor").clicked()
+                    {
+                        open_entry = Some((entry.path.clone(), entry.exercise.clone()));
+                    }
+                });
+            }
+        }
+        if let Some((path, exercise)) = open_entry {
+            self.confirm_or_discard(DiscardAction::Open(path, Box::new(exercise)));
+        }
+    }
+
+    fn show_programs_tab(&mut self, ui: &mut Ui) {
+        ui.heading("Training Programs");
+        ui.add_space(10.0);
+
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "{} programs in {}",
+                self.programs.len(),
+                self.programs_dir.display()
+            ));
+            if ui.button("Refresh").clicked() {
+                self.reload_programs();
+                match VolumeConfig::for_data_dir(&self.data_dir) {
+                    Ok(config) => self.volume_config = config,
+                    Err(e) => {
+                        self.status_message = format!(
+                            "Error reading {}: {}",
+                            VolumeConfig::user_path(&self.data_dir).display(),
+                            e
+                        );
+                    }
+                }
+            }
+        });
+
+        let mut open_program = None;
+        ui.horizontal(|ui| {
+            ui.label("Open:");
+            let selected = self
+                .program_path
+                .as_ref()
+                .and_then(|path| path.file_stem())
+                .map_or("Unsaved program".to_string(), |stem| stem.to_string_lossy().to_string());
+            egui::ComboBox::from_id_source("program_file_combo")
+                .selected_text(selected)
+                .width(220.0)
+                .show_ui(ui, |ui| {
+                    for (i, (path, program)) in self.programs.iter().enumerate() {
+                        let is_open = self.program_path.as_ref() == Some(path);
+                        if ui.selectable_label(is_open, &program.name).clicked() {
+                            open_program = Some(i);
+                        }
+                    }
+                });
+            if ui.button("New Program").clicked() {
+                self.program = Program::default();
+                self.program_path = None;
+                self.status_message = "Ready to build a new program".to_string();
+            }
+            if ui.button("Save Program").clicked() {
+                self.save_program();
+            }
+        });
+        if let Some(i) = open_program {
+            let (path, program) = self.programs[i].clone();
+            self.status_message = format!("Opened {}", path.display());
+            self.program = program;
+            self.program_path = Some(path);
+        }
+
+        ui.add_space(10.0);
+        ui.horizontal(|ui| {
+            ui.label("Program Name:");
+            ui.text_edit_singleline(&mut self.program.name);
+        });
+        ui.horizontal(|ui| {
+            ui.label("Notes:");
+            ui.text_edit_multiline(&mut self.program.notes);
+        });
+
+        let unknown = self.program.unknown_exercise_ids(&self.library);
+        if !unknown.is_empty() {
+            let ids: Vec<String> = unknown.iter().map(|id| format!("#{}", id)).collect();
+            ui.colored_label(
+                Color32::from_rgb(230, 180, 80),
+                format!("Not in the library: {}", ids.join(", ")),
+            );
+        }
+
+        let library = &self.library;
+        let program = &mut self.program;
+        let mut week_to_remove = None;
+        let mut week_to_duplicate = None;
+        for (w, week) in program.weeks.iter_mut().enumerate() {
+            let title = if week.deload {
+                format!("Week {} (deload)", w + 1)
+            } else {
+                format!("Week {}", w + 1)
+            };
+            egui::CollapsingHeader::new(RichText::new(title).strong())
+                .id_source(format!("program_week_{}", w))
+                .default_open(true)
+                .show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut week.deload, "Deload");
+                        if ui.button("Duplicate Week").clicked() {
+                            week_to_duplicate = Some(w);
+                        }
+                        if ui.button("Remove Week").clicked() {
+                            week_to_remove = Some(w);
+                        }
+                    });
+
+                    let mut session_to_remove = None;
+                    for (s, session) in week.sessions.iter_mut().enumerate() {
+                        ui.group(|ui| {
+                            ui.horizontal(|ui| {
+                                ui.label("Session:");
+                                ui.text_edit_singleline(&mut session.name);
+                                if ui.button("Remove Session").clicked() {
+                                    session_to_remove = Some(s);
+                                }
+                            });
+                            show_session_slots(ui, library, (w, s), session);
+                        });
+                    }
+                    if let Some(index) = session_to_remove {
+                        week.sessions.remove(index);
+                    }
+                    if ui.button("Add Session").clicked() {
+                        week.sessions.push(Session {
+                            name: format!("Session {}", week.sessions.len() + 1),
+                            slots: Vec::new(),
+                        });
+                    }
+                });
+        }
+        if let Some(index) = week_to_remove {
+            program.weeks.remove(index);
+        }
+        if let Some(index) = week_to_duplicate {
+            let copy = program.weeks[index].clone();
+            program.weeks.insert(index + 1, copy);
+        }
+        if ui.button("Add Week").clicked() {
+            program.weeks.push(Week::default());
+        }
+
+        ui.add_space(10.0);
+        egui::CollapsingHeader::new(RichText::new("Weekly Volume").strong())
+            .default_open(false)
+            .show(ui, |ui| self.show_program_volume(ui));
+    }
+
+    /// Hard sets per muscle and division in one week of the program, against
+    /// the volume landmarks.
+    fn show_program_volume(&mut self, ui: &mut Ui) {
+        let weeks = self.program.weekly_volume(&self.library, &self.volume_config);
+        if weeks.is_empty() {
+            ui.label("Add a week to see its volume.");
+            return;
+        }
+        self.volume_week = self.volume_week.min(weeks.len() - 1);
+        let week_label = |week: &WeekVolume| {
+            if week.deload {
+                format!("Week {} (deload)", week.week)
+            } else {
+                format!("Week {}", week.week)
+            }
+        };
+
+        ui.horizontal(|ui| {
+            ui.label("Week:");
+            egui::ComboBox::from_id_source("volume_week_combo")
+                .selected_text(week_label(&weeks[self.volume_week]))
+                .show_ui(ui, |ui| {
+                    for (i, week) in weeks.iter().enumerate() {
+                        ui.selectable_value(&mut self.volume_week, i, week_label(week));
+                    }
+                });
+        });
+        ui.label(
+            RichText::new(format!(
+                "Sets with at most {} RIR count. Secondary muscles get partial credit, and divisions a share by emphasis. Landmarks come from {}.",
+                self.volume_config.hard_set_max_rir,
+                VolumeConfig::user_path(&self.data_dir).display()
+            ))
+            .weak(),
+        );
+        let week = &weeks[self.volume_week];
+
+        egui::Grid::new("program_volume_grid").num_columns(6).striped(true).show(ui, |ui| {
+            for heading in ["Muscle", "Sets", "MEV", "MAV", "MRV", "Status"] {
+                ui.label(RichText::new(heading).strong());
+            }
+            ui.end_row();
+            for muscle in &week.muscles {
+                show_volume_row(ui, &muscle.muscle.name, &muscle.muscle);
+                for division in &muscle.divisions {
+                    show_volume_row(ui, &format!("    {}", division.name), division);
+                }
+            }
+        });
+
+        for (status, heading) in [(VolumeStatus::BelowMev, "Under-dosed"), (VolumeStatus::AboveMrv, "Over-dosed")] {
+            let names = week.with_status(status);
+            if !names.is_empty() {
+                ui.colored_label(volume_status_color(Some(status)), format!("{}: {}", heading, names.join(", ")));
+            }
+        }
+
+        // One bar per muscle, with short lines marking its MEV and MRV
+        let names: Vec<String> = week.muscles.iter().map(|m| m.muscle.name.clone()).collect();
+        let bars: Vec<Bar> = week
+            .muscles
+            .iter()
+            .enumerate()
+            .map(|(i, m)| {
+                Bar::new(i as f64, m.muscle.sets)
+                    .name(&m.muscle.name)
+                    .width(0.6)
+                    .fill(volume_status_color(m.muscle.status))
+            })
+            .collect();
+        let marker = |i: usize, sets: f64| Line::new(PlotPoints::new(vec![[i as f64 - 0.4, sets], [i as f64 + 0.4, sets]]));
+        Plot::new("program_volume_plot")
+            .height(220.0)
+            .legend(Legend::default())
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .include_y(0.0)
+            .x_axis_formatter(move |x, _| {
+                let i = x.round();
+                if (x - i).abs() < 1e-6 && i >= 0.0 {
+                    names.get(i as usize).cloned().unwrap_or_default()
+                } else {
+                    String::new()
+                }
+            })
+            .show(ui, |plot_ui| {
+                plot_ui.bar_chart(BarChart::new(bars).name("Hard sets"));
+                for (i, m) in week.muscles.iter().enumerate() {
+                    if let Some(landmarks) = &m.muscle.landmarks {
+                        plot_ui.line(marker(i, landmarks.mev).color(Color32::from_rgb(230, 180, 80)).name("MEV"));
+                        plot_ui.line(marker(i, landmarks.mrv).color(Color32::from_rgb(220, 90, 90)).name("MRV"));
+                    }
+                }
+            });
+    }
+
+    /// Models the external torque on one of the dynamic joints and can store
+    /// the result as that joint's resistance curve.
+    fn show_torque_calculator(&mut self, ui: &mut Ui) {
+        let dynamic: Vec<usize> = self
+            .exercise
+            .joints_involved
+            .joints
+            .iter()
+            .enumerate()
+            .filter(|(_, j)| j.dynamic)
+            .map(|(i, _)| i)
+            .collect();
+        let Some(&first_dynamic) = dynamic.first() else {
+            return;
+        };
+        if !dynamic.contains(&self.torque_joint) {
+            self.torque_joint = first_dynamic;
+        }
+
+        egui::CollapsingHeader::new(RichText::new("Torque Calculator").strong())
+            .id_source("torque_calculator")
+            .show(ui, |ui| {
+                let joints = &self.exercise.joints_involved.joints;
+                let model = &mut self.torque_model;
+
+                ui.horizontal(|ui| {
+                    ui.label("Joint:");
+                    egui::ComboBox::from_id_source("torque_joint_combo")
+                        .selected_text(format!("{}: {}", self.torque_joint + 1, joints[self.torque_joint].name))
+                        .width(150.0)
+                        .show_ui(ui, |ui| {
+                            for &i in &dynamic {
+                                ui.selectable_value(&mut self.torque_joint, i, format!("{}: {}", i + 1, joints[i].name));
+                            }
+                        });
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Load Source:");
+                    if ui.radio(matches!(model.source, LoadSource::FreeWeight), "Free Weight").clicked() {
+                        model.source = LoadSource::FreeWeight;
+                    }
+                    if ui.radio(matches!(model.source, LoadSource::Cable { .. }), "Cable").clicked() {
+                        model.source = LoadSource::Cable { pull_direction: 0.0 };
+                    }
+                    if ui.radio(matches!(model.source, LoadSource::Machine { .. }), "Machine").clicked() {
+                        let joint = &joints[self.torque_joint];
+                        let cam = [joint.angle_initial, joint.angle_final]
+                            .into_iter()
+                            .flatten()
+                            .map(|angle| CamPoint { angle, moment_arm: model.lever_length })
+                            .collect();
+                        model.source = LoadSource::Machine { cam };
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Load:");
+                    ui.add(egui::DragValue::new(&mut model.load).speed(0.5).clamp_range(0.0..=f64::MAX));
+                    if !matches!(model.source, LoadSource::Machine { .. }) {
+                        ui.label("Lever Length:");
+                        ui.add(egui::DragValue::new(&mut model.lever_length).speed(0.01).clamp_range(0.0..=f64::MAX));
+                    }
+                });
+
+                match &mut model.source {
+                    LoadSource::FreeWeight => {
+                        ui.horizontal(|ui| {
+                            ui.label("Posture:");
+                            ui.radio_value(&mut model.posture, Posture::Upright, "Upright");
+                            ui.radio_value(&mut model.posture, Posture::Lying, "Lying");
+                            ui.radio_value(&mut model.posture, Posture::SideLying, "Side Lying");
+                        });
+                    }
+                    LoadSource::Cable { pull_direction } => {
+                        ui.horizontal(|ui| {
+                            ui.label("Line of Pull:");
+                            ui.add(egui::DragValue::new(pull_direction).clamp_range(-180.0..=360.0).suffix("°"));
+                            ui.label(RichText::new("(0° pulls straight down, 90° horizontally)").weak());
+                        });
+                    }
+                    LoadSource::Machine { cam } => {
+                        let mut point_to_remove = None;
+                        egui::Grid::new("torque_cam_points").num_columns(3).show(ui, |ui| {
+                            ui.label("Joint Angle");
+                            ui.label("Moment Arm");
+                            ui.end_row();
+                            for (j, point) in cam.iter_mut().enumerate() {
+                                ui.add(egui::DragValue::new(&mut point.angle).clamp_range(0..=180).suffix("°"));
+                                ui.add(egui::DragValue::new(&mut point.moment_arm).speed(0.01));
+                                if ui.small_button("Remove").clicked() {
+                                    point_to_remove = Some(j);
+                                }
+                                ui.end_row();
+                            }
+                        });
+                        if let Some(j) = point_to_remove {
+                            cam.remove(j);
+                        }
+                        if ui.button("Add Cam Point").clicked() {
+                            let last = cam.last().copied();
+                            cam.push(last.unwrap_or(CamPoint { angle: 90, moment_arm: 1.0 }));
+                        }
+                    }
+                }
+
+                if !matches!(model.source, LoadSource::Machine { .. }) {
+                    ui.horizontal(|ui| {
+                        ui.label("Segment Direction at Start:");
+                        ui.add(egui::DragValue::new(&mut model.segment_initial).clamp_range(-180.0..=360.0).suffix("°"));
+                        let mut custom_end = model.segment_final.is_some();
+                        if ui.checkbox(&mut custom_end, "at End:").changed() {
+                            model.segment_final = custom_end.then_some(model.segment_initial);
+                        }
+                        if let Some(segment_final) = &mut model.segment_final {
+                            ui.add(egui::DragValue::new(segment_final).clamp_range(-180.0..=360.0).suffix("°"));
+                        }
+                    });
+                }
+
+                let joint = &joints[self.torque_joint];
+                let Some(analysis) = model.analyze(joint, &self.exercise.plane_of_motion) else {
+                    ui.label(RichText::new("The joint needs an initial and a final angle").weak());
+                    return;
+                };
+
+                let profile = analysis
+                    .profile
+                    .as_ref()
+                    .map_or("none (the load never resists the movement)".to_string(), |p| format!("{:?}", p));
+                ui.label(format!("Profile: {}", profile));
+                if let (Some(peak), Some(position)) = (analysis.peak, analysis.peak_position) {
+                    ui.label(format!("Peak: {:.2} at {}° ({})", peak.torque, peak.angle, position.label()));
+                }
+
+                Plot::new("torque_calculator_plot")
+                    .height(200.0)
+                    .legend(Legend::default())
+                    .include_y(0.0)
+                    .allow_drag(false)
+                    .allow_scroll(false)
+                    .show(ui, |plot_ui| {
+                        let torque: Vec<[f64; 2]> = analysis.points.iter().map(|p| [p.angle as f64, p.torque]).collect();
+                        let moment_arm: Vec<[f64; 2]> =
+                            analysis.points.iter().map(|p| [p.angle as f64, p.moment_arm]).collect();
+                        plot_ui.line(Line::new(PlotPoints::from(torque)).name("Torque"));
+                        plot_ui.line(Line::new(PlotPoints::from(moment_arm)).name("Moment Arm"));
+                    });
+
+                if ui.button("Use as Resistance Curve").clicked() {
+                    let joint_name = joint.name.clone();
+                    self.exercise.joints_involved.joints[self.torque_joint].resistance_curve = Some(analysis.to_curve());
+                    self.exercise.sync_resistance_profile();
+                    self.status_message = format!("Stored the calculated torque as the {} resistance curve.", joint_name);
+                }
+            });
+    }
+
+    /// Plots the curves of the exercise being edited, optionally against a
+    /// library exercise, with the torque of each at a chosen angle.
+    fn show_curve_comparison(&mut self, ui: &mut Ui) {
+        let mut curves: Vec<(String, ResistanceCurve)> = self
+            .exercise
+            .joints_involved
+            .joints
+            .iter()
+            .filter(|j| j.dynamic)
+            .filter_map(|j| Some((format!("This exercise ({})", j.name), j.resistance_curve.clone()?)))
+            .collect();
+        let comparable: Vec<&Exercise> = self
+            .library
+            .entries
+            .iter()
+            .map(|e| &e.exercise)
+            .filter(|e| e.curve_joint().is_some() && (self.source_path.is_none() || e.id != self.exercise.id))
+            .collect();
+        if curves.is_empty() && comparable.is_empty() {
+            return;
+        }
+
+        ui.group(|ui| {
+            ui.label(RichText::new("Resistance Curve").strong());
+            ui.horizontal(|ui| {
+                ui.label("Compare with:");
+                let selected = comparable
+                    .iter()
+                    .find(|e| Some(e.id) == self.curve_compare_id)
+                    .map_or("None".to_string(), |e| e.name.clone());
+                egui::ComboBox::from_id_source("curve_compare_combo")
+                    .selected_text(selected)
+                    .width(200.0)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.curve_compare_id, None, "None");
+                        for exercise in &comparable {
+                            ui.selectable_value(&mut self.curve_compare_id, Some(exercise.id), &exercise.name);
+                        }
+                    });
+                ui.checkbox(&mut self.curve_normalized, "Normalize to peak");
+            });
+
+            if let Some(exercise) = comparable.iter().find(|e| Some(e.id) == self.curve_compare_id)
+                && let Some(joint) = exercise.curve_joint()
+                && let Some(curve) = &joint.resistance_curve
+            {
+                curves.push((format!("{} ({})", exercise.name, joint.name), curve.clone()));
+            }
+            if self.curve_normalized {
+                for (_, curve) in &mut curves {
+                    *curve = curve.normalized();
+                }
+            }
+
+            ui.horizontal(|ui| {
+                ui.label("Torque at:");
+                ui.add(egui::Slider::new(&mut self.curve_probe_angle, 0..=180).suffix("°"));
+            });
+            for (name, curve) in &curves {
+                let torque = curve
+                    .torque_at(self.curve_probe_angle as f64)
+                    .map_or("outside sampled range".to_string(), |t| format!("{:.2}", t));
+                ui.label(format!("{}: {}", name, torque));
+            }
+
+            Plot::new("resistance_curve_plot")
+                .height(220.0)
+                .legend(Legend::default())
+                .include_x(0.0)
+                .include_x(180.0)
+                .include_y(0.0)
+                .allow_drag(false)
+                .allow_scroll(false)
+                .show(ui, |plot_ui| {
+                    for (name, curve) in &curves {
+                        let points: Vec<[f64; 2]> = curve
+                            .sorted()
+                            .iter()
+                            .map(|s| [s.angle as f64, s.torque])
+                            .collect();
+                        plot_ui.line(Line::new(PlotPoints::from(points)).name(name));
+                    }
+                    plot_ui.vline(VLine::new(self.curve_probe_angle as f64).color(Color32::GRAY));
+                });
+        });
+    }
+
+    fn show_library_filters(&mut self, ui: &mut Ui) {
+        let muscles: Vec<&String> = self.taxonomy.muscles.iter().map(|m| &m.name).collect();
+        let query = &mut self.library_query;
+
+        ui.group(|ui| {
+            egui::Grid::new("library_filters").num_columns(4).spacing([20.0, 6.0]).show(ui, |ui| {
+                ui.label("Muscle:");
+                optional_text_combo(ui, "filter_muscle", &mut query.muscle_name, &muscles);
+                ui.label("Active Division:");
+                let mut divisions: Vec<&String> = match &query.muscle_name {
+                    Some(muscle) => self.taxonomy.divisions(muscle).iter().collect(),
+                    None => self.taxonomy.muscles.iter().flat_map(|m| &m.divisions).collect(),
+                };
+                divisions.sort();
+                divisions.dedup();
+                optional_text_combo(ui, "filter_division", &mut query.division, &divisions);
+                ui.end_row();
+
+                ui.label("Joint:");
+                let joints: Vec<&String> = self.taxonomy.joints.iter().map(|j| &j.name).collect();
+                optional_text_combo(ui, "filter_joint", &mut query.joint, &joints);
+                ui.label("Joint Use:");
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut query.joint_dynamic, None, "Any");
+                    ui.radio_value(&mut query.joint_dynamic, Some(true), "Dynamic");
+                    ui.radio_value(&mut query.joint_dynamic, Some(false), "Static");
+                });
+                ui.end_row();
+
+                ui.label("Joint Action:");
+                let actions: Vec<(JointAction, &str)> = match query.joint.as_deref().and_then(|j| self.taxonomy.joint(j)) {
+                    Some(spec) => spec.allowed_actions().iter().map(|a| (*a, a.label())).collect(),
+                    None => JointAction::ALL.iter().map(|a| (*a, a.label())).collect(),
+                };
+                optional_enum_combo(ui, "filter_joint_action", &mut query.joint_action, &actions);
+                ui.end_row();
+
+                ui.label("Plane:");
+                optional_enum_combo(ui, "filter_plane", &mut query.plane_of_motion, &[
+                    (PlaneOfMotion::Sagittal, "Sagittal"),
+                    (PlaneOfMotion::Frontal, "Frontal"),
+                    (PlaneOfMotion::Transverse, "Transverse"),
+                ]);
+                ui.label("Profile:");
+                optional_enum_combo(ui, "filter_profile", &mut query.resistance_profile, &[
+                    (ResistanceProfile::Ascending, "Ascending"),
+                    (ResistanceProfile::Descending, "Descending"),
+                    (ResistanceProfile::Bell, "Bell"),
+                    (ResistanceProfile::Constant, "Constant"),
+                ]);
+                ui.end_row();
+
+                ui.label("Equipment:");
+                let equipment: Vec<(Equipment, &str)> = Equipment::ALL.iter().map(|e| (*e, e.label())).collect();
+                optional_enum_combo(ui, "filter_equipment", &mut query.equipment, &equipment);
+                ui.end_row();
+
+                ui.label("Type:");
+                optional_enum_combo(ui, "filter_type", &mut query.exercise_type, &[
+                    (ExerciseType::Compound, "Compound"),
+                    (ExerciseType::Isolation, "Isolation"),
+                ]);
+                ui.label("Text:");
+                let mut text = query.text.clone().unwrap_or_default();
+                if ui.text_edit_singleline(&mut text).changed() {
+                    query.text = if text.is_empty() { None } else { Some(text) };
+                }
+                ui.end_row();
+
+                ui.label("Min Stretch:");
+                ui.horizontal(|ui| {
+                    let mut enabled = query.min_stretch.is_some();
+                    if ui.checkbox(&mut enabled, "").changed() {
+                        query.min_stretch = enabled.then_some(0.5);
+                    }
+                    if let Some(min) = &mut query.min_stretch {
+                        let mut percent = (*min * 100.0).round() as u32;
+                        if ui.add(egui::Slider::new(&mut percent, 0..=100).suffix("%")).changed() {
+                            *min = percent as f64 / 100.0;
+                        }
+                    }
+                });
+                ui.end_row();
+            });
+
+            if !query.is_empty() && ui.button("Clear Filters").clicked() {
+                *query = ExerciseQuery::default();
+            }
+        });
+    }
+
+    fn renumber_duplicates(&mut self) {
+        match self.library.renumber_duplicates() {
+            Ok(changes) => {
+                let summary: Vec<String> = changes
+                    .iter()
+                    .map(|c| format!("#{} -> #{}", c.old_id, c.new_id))
+                    .collect();
+                self.status_message = format!("Renumbered {} exercise(s): {}", changes.len(), summary.join(", "));
+                if let Some(change) = changes.iter().find(|c| Some(&c.path) == self.source_path.as_ref()) {
+                    self.exercise.id = change.new_id;
+                    self.saved_exercise.id = change.new_id;
+                    self.source_modified = modified_time(&change.path);
+                }
+            }
+            Err(e) => {
+                self.status_message = format!("Error renumbering exercises: {}", e);
+            }
+        }
+    }
+
+    fn reload_taxonomy(&mut self) {
+        match Taxonomy::for_data_dir(&self.data_dir) {
+            Ok(taxonomy) => {
+                self.status_message = format!(
+                    "Loaded {} muscles and {} joints",
+                    taxonomy.muscles.len(),
+                    taxonomy.joints.len()
+                );
+                self.taxonomy = taxonomy;
+            }
+            Err(e) => {
+                self.status_message = format!(
+                    "Error reading {}: {}",
+                    Taxonomy::user_path(&self.data_dir).display(),
+                    e
+                );
+            }
+        }
+    }
+
+    fn reload_library(&mut self) {
+        match Library::load(&self.data_dir) {
+            Ok(library) => {
+                self.status_message = format!("Loaded {} exercises", library.len());
+                self.library = library;
+                self.selected_library_entry = None;
+            }
+            Err(e) => {
+                self.status_message = format!("Error reading data directory: {}", e);
+            }
+        }
+    }
+
+    fn reload_programs(&mut self) {
+        match load_programs(&self.programs_dir) {
+            Ok(programs) => {
+                let mut failures = Vec::new();
+                self.programs.clear();
+                for (path, program) in programs {
+                    match program {
+                        Ok(program) => self.programs.push((path, program)),
+                        Err(e) => failures.push(format!("{}: {}", path.display(), e)),
+                    }
+                }
+                self.status_message = if failures.is_empty() {
+                    format!("Loaded {} programs", self.programs.len())
+                } else {
+                    format!("Loaded {} programs, could not read {}", self.programs.len(), failures.join("; "))
+                };
+            }
+            Err(e) => {
+                self.status_message = format!("Error reading {}: {}", self.programs_dir.display(), e);
+            }
+        }
+    }
+
+    /// Saves back to the program's file, or to a new file named after it.
+    /// Refuses to replace a different program that has the same name.
+    fn save_program(&mut self) {
+        if self.program.name.trim().is_empty() {
+            self.status_message = "Cannot save: the program needs a name".to_string();
+            return;
+        }
+        let path = match &self.program_path {
+            Some(path) => path.clone(),
+            None => {
+                let path = self.program.default_path(&self.programs_dir);
+                if path.exists() {
+                    self.status_message = format!("Cannot save: {} already exists", path.display());
+                    return;
+                }
+                path
+            }
+        };
+        if let Err(e) = std::fs::create_dir_all(&self.programs_dir) {
+            self.status_message = format!("Error creating {}: {}", self.programs_dir.display(), e);
+            return;
+        }
+
+        match self.program.save(&path) {
+            Ok(()) => {
+                self.reload_programs();
+                self.status_message = format!("Program '{}' saved to {}", self.program.name, path.display());
+                self.program_path = Some(path);
+            }
+            Err(e) => {
+                self.status_message = format!("Error saving program: {}", e);
+            }
+        }
+    }
+
+    fn show_save_dialog(&mut self, ctx: &egui::Context) {
+        let file_path = self
+            .save_dir
+            .join(format!("{}.json", sanitize_filename(&self.save_filename)));
+        let collides = file_path.exists() && self.source_path.as_ref() != Some(&file_path);
+
+        egui::Window::new("Save Exercise")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label("Enter a filename to save the exercise:");
+                ui.text_edit_singleline(&mut self.save_filename);
+
+                ui.horizontal(|ui| {
+                    ui.label("Folder:");
+                    ui.label(RichText::new(self.save_dir.display().to_string()).monospace());
+                    if ui.button("Choose...").clicked()
+                        && let Some(dir) = rfd::FileDialog::new().set_directory(&self.save_dir).pick_folder()
+                    {
+                        self.save_dir = dir;
+                    }
+                });
+                ui.label(RichText::new(format!("Saves as {}", file_path.display())).weak());
+                if collides {
+                    ui.colored_label(
+                        Color32::from_rgb(230, 180, 80),
+                        "A file with this name already exists and will be overwritten",
+                    );
+                }
+                if self.save_dir != self.data_dir {
+                    ui.label(RichText::new("Exercises outside the data folder do not appear in the library").weak());
+                }
+
+                ui.horizontal(|ui| {
+                    if ui.button("Cancel").clicked() {
+                        self.show_save_dialog = false;
+                    }
+
+                    if ui.button("Save").clicked() {
+                        self.show_save_dialog = false;
+                        self.save_exercise(file_path);
+                    }
+                });
+            });
+    }
+
+    /// Ctrl+Z undoes, Ctrl+Shift+Z or Ctrl+Y redoes.
+    fn handle_undo_shortcuts(&mut self, ctx: &egui::Context) {
+        let (undo, redo) = ctx.input_mut(|i| {
+            let redo = i.consume_key(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::Z)
+                || i.consume_key(egui::Modifiers::COMMAND, egui::Key::Y);
+            let undo = i.consume_key(egui::Modifiers::COMMAND, egui::Key::Z);
+            (undo, redo)
+        });
+        if undo {
+            self.undo();
+        }
+        if redo {
+            self.redo();
+        }
+    }
+
+    fn undo(&mut self) {
+        match self.history.undo(&self.exercise) {
+            Some(previous) => {
+                self.exercise = previous;
+                self.status_message = "Undid the last change".to_string();
+            }
+            None => self.status_message = "Nothing to undo".to_string(),
+        }
+    }
+
+    fn redo(&mut self) {
+        match self.history.redo(&self.exercise) {
+            Some(next) => {
+                self.exercise = next;
+                self.status_message = "Redid the last undone change".to_string();
+            }
+            None => self.status_message = "Nothing to redo".to_string(),
+        }
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.exercise != self.saved_exercise
+    }
+
+    /// Runs `action` straight away when there is nothing to lose, otherwise
+    /// asks first.
+    fn confirm_or_discard(&mut self, action: DiscardAction) {
+        if self.is_dirty() {
+            self.confirm_discard = Some(action);
+        } else {
+            self.discard(action);
+        }
+    }
+
+    fn discard(&mut self, action: DiscardAction) {
+        match action {
+            DiscardAction::NewExercise => self.new_exercise(),
+            DiscardAction::Open(path, exercise) => self.load_into_editor(path, *exercise),
+            DiscardAction::Close => self.allowed_to_close = true,
+        }
+    }
+
+    fn show_discard_dialog(&mut self, ctx: &egui::Context) {
+        let question = match &self.confirm_discard {
+            Some(DiscardAction::NewExercise) => "Start a new exercise?".to_string(),
+            Some(DiscardAction::Open(path, _)) => format!("Open {}?", path.display()),
+            Some(DiscardAction::Close) => "Quit the archive?".to_string(),
+            None => return,
+        };
+
+        egui::Window::new("Unsaved Changes")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!("'{}' has unsaved changes that will be lost.", self.exercise.name));
+                ui.label(question);
+                ui.horizontal(|ui| {
+                    if ui.button("Cancel").clicked() {
+                        self.confirm_discard = None;
+                    }
+                    if ui.button("Discard Changes").clicked()
+                        && let Some(action) = self.confirm_discard.take()
+                    {
+                        self.discard(action);
+                    }
+                });
+            });
+    }
+
+    fn show_overwrite_dialog(&mut self, ctx: &egui::Context) {
+        let Some(path) = self.confirm_overwrite.clone() else {
+            return;
+        };
+        let reason = if self.source_path.as_ref() == Some(&path) {
+            "was changed on disk since it was opened"
+        } else {
+            "already exists"
+        };
+
+        egui::Window::new("Overwrite File")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!("{} {}.", path.display(), reason));
+                ui.label("Overwrite it?");
+                ui.horizontal(|ui| {
+                    if ui.button("Cancel").clicked() {
+                        self.confirm_overwrite = None;
+                        self.status_message = "Save cancelled".to_string();
+                    }
+                    if ui.button("Overwrite").clicked() {
+                        self.confirm_overwrite = None;
+                        self.write_exercise(&path);
+                    }
+                });
+            });
+    }
+
+    fn new_exercise(&mut self) {
+        self.exercise = Exercise::default();
+        self.history.reset(&self.exercise);
+        self.saved_exercise = self.exercise.clone();
+        self.source_path = None;
+        self.source_modified = None;
+        self.current_tab = Tab::BasicInfo;
+        self.status_message = "Ready to create a new exercise".to_string();
+    }
+
+    fn open_exercise_dialog(&mut self) {
+        let picked = rfd::FileDialog::new()
+            .add_filter("Exercise JSON", &["json"])
+            .set_directory(&self.data_dir)
+            .pick_file();
+
+        if let Some(path) = picked {
+            match read_exercise(&path) {
+                Ok(exercise) => self.confirm_or_discard(DiscardAction::Open(path, Box::new(exercise))),
+                Err(e) => {
+                    self.status_message = format!("Error opening {}: {}", path.display(), e);
+                }
+            }
+        }
+    }
+
+    fn load_into_editor(&mut self, path: PathBuf, exercise: Exercise) {
+        self.exercise = exercise;
+        self.history.reset(&self.exercise);
+        self.saved_exercise = self.exercise.clone();
+        self.status_message = format!("Opened {}", path.display());
+        self.source_modified = modified_time(&path);
+        self.source_path = Some(path);
+        self.current_tab = Tab::BasicInfo;
+    }
+
+    /// Saves straight back to the opened file, or asks for a filename for new exercises.
+    fn request_save(&mut self) {
+        let report = self.exercise.validate_with(&self.taxonomy);
+        if report.has_errors() {
+            self.status_message = format!(
+                "Cannot save: {} validation error(s), see Preview",
+                report.errors().count()
+            );
+            self.current_tab = Tab::Preview;
+            return;
+        }
+
+        match self.source_path.clone() {
+            // Someone else may have saved over the file in the meantime
+            Some(path) if path.exists() && modified_time(&path) != self.source_modified => {
+                self.confirm_overwrite = Some(path);
+            }
+            Some(path) => self.write_exercise(&path),
+            None => {
+                self.save_filename = self.exercise.name.clone();
+                self.show_save_dialog = true;
+            }
+        }
+    }
+
+    fn save_exercise(&mut self, file_path: PathBuf) {
+        if let Some(dir) = file_path.parent()
+            && let Err(e) = std::fs::create_dir_all(dir)
+        {
+            self.status_message = format!("Error creating {}: {}", dir.display(), e);
+            return;
+        }
+
+        if file_path.exists() && self.source_path.as_ref() != Some(&file_path) {
+            self.confirm_overwrite = Some(file_path);
+            return;
+        }
+        self.write_exercise(&file_path);
+    }
+
+    fn write_exercise(&mut self, file_path: &Path) {
+        // A new exercise gets the id the Basic Info tab has been showing
+        let id = match self.source_path {
+            Some(_) => self.library.assign_id(self.exercise.id, file_path),
+            None => self.library.next_free_id(),
+        };
+        let id = match id {
+            Ok(id) => id,
+            Err(e) => {
+                self.status_message = format!("Cannot save: {}", e);
+                return;
+            }
+        };
+        // The editor keeps its id until the file is actually written
+        let exercise = Exercise { id, ..self.exercise.clone() };
+
+        let backup = if self.keep_backups {
+            match backup_file(file_path) {
+                Ok(backup) => backup,
+                Err(e) => {
+                    self.status_message = format!("Error backing up {}: {}", file_path.display(), e);
+                    return;
+                }
+            }
+        } else {
+            None
+        };
+
+        match write_exercise(file_path, &exercise) {
+            Ok(()) => {
+                self.exercise.id = id;
+                self.source_path = Some(file_path.to_path_buf());
+                self.source_modified = modified_time(file_path);
+                self.saved_exercise = exercise;
+                self.reload_library();
+                self.status_message = format!("Exercise #{} saved to {}", id, file_path.display());
+                if let Some(backup) = backup {
+                    self.status_message += &format!(" (previous version in {})", backup.display());
+                }
+            }
+            Err(e) => {
+                self.status_message = format!("Error saving exercise: {}", e);
+            }
+        }
+    }
+}
+
+/// The exercises of one program session, with a picker to add more from the library.
+fn show_session_slots(ui: &mut Ui, library: &Library, (week, session_index): (usize, usize), session: &mut Session) {
+    let id = format!("program_slots_{}_{}", week, session_index);
+    let mut slot_to_remove = None;
+    let mut slot_to_raise = None;
+    if !session.slots.is_empty() {
+        egui::Grid::new(&id).num_columns(7).spacing([10.0, 4.0]).show(ui, |ui| {
+            ui.label(RichText::new("Exercise").strong());
+            ui.label(RichText::new("Sets").strong());
+            ui.label(RichText::new("Reps").strong());
+            ui.label(RichText::new("RIR").strong());
+            ui.label(RichText::new("Rest").strong());
+            ui.label(RichText::new("Notes").strong());
+            ui.label("");
+            ui.end_row();
+
+            for (i, slot) in session.slots.iter_mut().enumerate() {
+                match library.find_by_id(slot.exercise_id) {
+                    Some(entry) => ui.label(format!("#{} {}", slot.exercise_id, entry.exercise.name)),
+                    None => ui.colored_label(Color32::from_rgb(230, 180, 80), format!("#{} (missing)", slot.exercise_id)),
+                };
+                ui.add(egui::DragValue::new(&mut slot.sets).clamp_range(1..=20));
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut slot.reps.min).clamp_range(1..=100));
+                    ui.label("-");
+                    ui.add(egui::DragValue::new(&mut slot.reps.max).clamp_range(slot.reps.min..=100));
+                });
+                ui.add(egui::DragValue::new(&mut slot.rir).clamp_range(0..=10));
+                ui.add(egui::DragValue::new(&mut slot.rest_seconds).clamp_range(0..=600).speed(5).suffix(" s"));
+                ui.text_edit_singleline(&mut slot.notes);
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(i > 0, egui::Button::new("Up")).clicked() {
+                        slot_to_raise = Some(i);
+                    }
+                    if ui.button("Remove").clicked() {
+                        slot_to_remove = Some(i);
+                    }
+                });
+                ui.end_row();
+            }
+        });
+    }
+    if let Some(i) = slot_to_raise {
+        session.slots.swap(i - 1, i);
+    }
+    if let Some(i) = slot_to_remove {
+        session.slots.remove(i);
+    }
+
+    egui::ComboBox::from_id_source(format!("{}_add", id))
+        .selected_text("Add exercise...")
+        .width(260.0)
+        .show_ui(ui, |ui| {
+            for entry in &library.entries {
+                let label = format!("#{} {}", entry.exercise.id, entry.exercise.name);
+                if ui.selectable_label(false, label).clicked() {
+                    session.slots.push(Slot::new(entry.exercise.id));
+                }
+            }
+        });
+}
+
+/// Set-by-set editor for one exercise of a logged session.
+fn show_logged_sets(ui: &mut Ui, index: usize, logged: &mut LoggedExercise) {
+    let mut set_to_remove = None;
+    egui::Grid::new(format!("logged_sets_{}", index)).num_columns(5).show(ui, |ui| {
+        ui.label(RichText::new("Set").strong());
+        ui.label(RichText::new("Reps").strong());
+        ui.label(RichText::new("Load").strong());
+        ui.label(RichText::new("RIR").strong());
+        ui.label("");
+        ui.end_row();
+        for (j, set) in logged.sets.iter_mut().enumerate() {
+            ui.label((j + 1).to_string());
+            ui.add(egui::DragValue::new(&mut set.reps).clamp_range(0..=100));
+            ui.add(egui::DragValue::new(&mut set.load).clamp_range(0.0..=2000.0).speed(0.5));
+            ui.horizontal(|ui| {
+                let mut rated = set.rir.is_some();
+                if ui.checkbox(&mut rated, "").changed() {
+                    set.rir = rated.then_some(2);
+                }
+                if let Some(rir) = &mut set.rir {
+                    ui.add(egui::DragValue::new(rir).clamp_range(0..=10));
+                }
+            });
+            if ui.button("Remove").clicked() {
+                set_to_remove = Some(j);
+            }
+            ui.end_row();
+        }
+    });
+    if let Some(j) = set_to_remove {
+        logged.sets.remove(j);
+    }
+    if ui.button("Add Set").clicked() {
+        let set = logged.sets.last().copied().unwrap_or(LoggedSet { reps: 10, load: 0.0, rir: None });
+        logged.sets.push(set);
+    }
+}
+
+fn show_volume_row(ui: &mut Ui, name: &str, volume: &Volume) {
+    ui.label(name);
+    ui.label(format!("{:.1}", volume.sets));
+    match &volume.landmarks {
+        Some(landmarks) => {
+            ui.label(landmarks.mev.to_string());
+            ui.label(landmarks.mav.to_string());
+            ui.label(landmarks.mrv.to_string());
+        }
+        None => {
+            ui.label("-");
+            ui.label("-");
+            ui.label("-");
+        }
+    }
+    ui.colored_label(volume_status_color(volume.status), volume.status.map_or("", |s| s.label()));
+    ui.end_row();
+}
+
+fn volume_status_color(status: Option<VolumeStatus>) -> Color32 {
+    match status {
+        Some(VolumeStatus::BelowMev) => Color32::from_rgb(230, 180, 80),
+        Some(VolumeStatus::MevToMav) | Some(VolumeStatus::MavToMrv) => Color32::from_rgb(100, 200, 120),
+        Some(VolumeStatus::AboveMrv) => Color32::from_rgb(220, 90, 90),
+        None => Color32::from_rgb(120, 200, 255),
+    }
+}
+
+/// Dates on plot axes are days since the start of the common era.
+fn day_number(date: chrono::NaiveDate) -> f64 {
+    date.num_days_from_ce() as f64
+}
+
+fn day_label(x: f64) -> String {
+    if (x - x.round()).abs() > 1e-6 {
+        return String::new();
+    }
+    chrono::NaiveDate::from_num_days_from_ce_opt(x.round() as i32).map_or(String::new(), |d| d.format("%b %d").to_string())
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Read-only summary of an exercise, shared by the Preview and Library tabs.
+fn show_exercise_summary(ui: &mut Ui, exercise: &Exercise) {
+    ui.group(|ui| {
+        ui.label(format!("Name: {}", exercise.name));
+        ui.label(format!("Type: {:?}", exercise.r#type));
+        ui.label(format!("Description: {}", exercise.description));
+        ui.label(format!("Plane of Motion: {:?}", exercise.plane_of_motion));
+        ui.label(format!("Resistance Profile: {:?}", exercise.resistance_profile));
+        
+        ui.add_space(5.0);
+        ui.label("Target Muscles:");
+        for muscle in &exercise.target_muscles.muscles {
+            let divisions: Vec<String> = muscle
+                .active_divisions()
+                .map(|d| format!("{} ({:?})", d.name, d.emphasis))
+                .collect();
+            ui.label(format!(
+                "- {} [{:?}]: {}",
+                muscle.muscle_name,
+                muscle.role,
+                divisions.join(", ")
+            ));
+        }
+        
+        ui.add_space(5.0);
+        ui.label("Joints Involved:");
+        for joint in &exercise.joints_involved.joints {
+            if joint.dynamic {
+                ui.label(format!(
+                    "- {} (Dynamic): {} from {}° to {}°",
+                    joint.name,
+                    joint.direction.map_or("?", |a| a.label()),
+                    joint.angle_initial.unwrap_or(0),
+                    joint.angle_final.unwrap_or(0)
+                ));
+            } else {
+                ui.label(format!(
+                    "- {} (Static): {}°",
+                    joint.name,
+                    joint.angle.unwrap_or(0)
+                ));
+            }
+        }
+        
+        ui.add_space(5.0);
+        ui.label(format!("Tips: {}", exercise.tips));
+        ui.label(format!("Video: {}", exercise.technique_video));
+
+        if !exercise.variants.is_empty() {
+            ui.add_space(5.0);
+            ui.label("Variants:");
+            for variant in &exercise.variants {
+                let notes = if variant.notes.is_empty() { String::new() } else { format!(" - {}", variant.notes) };
+                ui.label(format!("- {}: {:?}{}", variant.label(), variant.resistance_profile, notes));
+            }
+        }
+    });
+}
+
+/// How much of the load each targeted division takes while it is long.
+fn show_stretch_scores(ui: &mut Ui, exercise: &Exercise, taxonomy: &Taxonomy) {
+    ui.group(|ui| {
+        ui.label(RichText::new("Loaded at Long Length").strong());
+        let scores = exercise.stretch_scores(taxonomy);
+        if scores.is_empty() {
+            ui.label(RichText::new("No length model covers the targeted divisions and listed joints.").weak());
+            return;
+        }
+        egui::Grid::new("stretch_scores").num_columns(3).show(ui, |ui| {
+            for score in &scores {
+                ui.label(format!("{} - {}", score.muscle, score.division));
+                ui.add(
+                    egui::ProgressBar::new(score.score as f32)
+                        .desired_width(200.0)
+                        .text(format!("{:.0}%", score.score * 100.0)),
+                );
+                ui.label(RichText::new(format!("reaches {:.0}% of full length", score.longest * 100.0)).weak());
+                ui.end_row();
+            }
+        });
+    });
+}
+
+fn show_validation_report(ui: &mut Ui, report: &ValidationReport) {
+    ui.group(|ui| {
+        if report.is_clean() {
+            ui.label(RichText::new("No validation issues").color(Color32::from_rgb(120, 220, 120)));
+            return;
+        }
+
+        ui.label(format!(
+            "Validation: {} error(s), {} warning(s)",
+            report.errors().count(),
+            report.warnings().count()
+        ));
+        for issue in &report.issues {
+            let (prefix, color) = match issue.severity() {
+                Severity::Error => ("Error", Color32::from_rgb(255, 120, 120)),
+                Severity::Warning => ("Warning", Color32::from_rgb(255, 200, 100)),
+            };
+            ui.label(RichText::new(format!("{}: {}", prefix, issue)).color(color));
+        }
+    });
+}
+
+/// Combo box over a list of names with an extra "Any" entry for `None`.
+fn optional_text_combo(ui: &mut Ui, id: &str, value: &mut Option<String>, options: &[&String]) {
+    egui::ComboBox::from_id_source(id)
+        .selected_text(value.clone().unwrap_or_else(|| "Any".to_string()))
+        .width(150.0)
+        .show_ui(ui, |ui| {
+            ui.selectable_value(value, None, "Any");
+            for option in options {
+                ui.selectable_value(value, Some((*option).clone()), option.as_str());
+            }
+        });
+}
+
+/// Sample table for a dynamic joint's resistance curve.
+fn show_curve_editor(ui: &mut Ui, id: usize, joint: &mut Joint) {
+    let from = joint.angle_initial.unwrap_or(0);
+    let to = joint.angle_final.unwrap_or(0);
+
+    let Some(curve) = &mut joint.resistance_curve else {
+        if ui.button("Add Curve").clicked() {
+            joint.resistance_curve = Some(ResistanceCurve::sampled(from, to, 7, |_| 1.0));
+        }
+        return;
+    };
+
+    let mut sample_to_remove = None;
+    egui::Grid::new(format!("curve_samples_{}", id)).num_columns(3).show(ui, |ui| {
+        ui.label("Angle");
+        ui.label("Torque");
+        ui.end_row();
+        for (j, sample) in curve.samples.iter_mut().enumerate() {
+            ui.add(egui::DragValue::new(&mut sample.angle).clamp_range(0..=180).suffix("°"));
+            ui.add(egui::DragValue::new(&mut sample.torque).speed(0.05).clamp_range(0.0..=f64::MAX));
+            if ui.small_button("Remove").clicked() {
+                sample_to_remove = Some(j);
+            }
+            ui.end_row();
+        }
+    });
+    if let Some(j) = sample_to_remove {
+        curve.samples.remove(j);
+    }
+
+    let mut remove_curve = false;
+    ui.horizontal(|ui| {
+        if ui.button("Add Sample").clicked() {
+            let last = curve.samples.last().copied();
+            curve.samples.push(last.unwrap_or(TorqueSample { angle: to, torque: 1.0 }));
+        }
+        if ui.button("Resample Range").clicked() {
+            let old = curve.clone();
+            *curve = ResistanceCurve::sampled(from, to, 7, |angle| old.torque_at(angle as f64).unwrap_or(1.0));
+        }
+        if ui.button("Remove Curve").clicked() {
+            remove_curve = true;
+        }
+    });
+    if remove_curve {
+        joint.resistance_curve = None;
+        return;
+    }
+
+    match joint.derived_resistance_profile() {
+        Some(profile) => ui.label(format!("Derived profile: {:?}", profile)),
+        None => ui.label(RichText::new("Needs two samples with some torque to derive a profile").weak()),
+    };
+}
+
+/// Combo box over enum variants with an extra "Any" entry for `None`.
+fn optional_enum_combo<T: PartialEq + Clone>(ui: &mut Ui, id: &str, value: &mut Option<T>, options: &[(T, &str)]) {
+    let selected = options
+        .iter()
+        .find(|(option, _)| Some(option) == value.as_ref())
+        .map_or("Any", |(_, label)| label);
+    egui::ComboBox::from_id_source(id)
+        .selected_text(selected)
+        .width(150.0)
+        .show_ui(ui, |ui| {
+            ui.selectable_value(value, None, "Any");
+            for (option, label) in options {
+                ui.selectable_value(value, Some(option.clone()), *label);
+            }
+        });
+}
@@ -0,0 +1,162 @@
+//! Snapshot-based undo and redo for the editor.
+//!
+//! The editor feeds the state it is editing to [`History::track`] once per
+//! frame. A run of edits (typing a word, dragging a slider) becomes one undo
+//! step once the state has stopped changing for [`STABLE_TIME`] seconds, and
+//! undoing in the middle of a run first records the run so it can be redone.
+
+/// Seconds without a change after which pending edits become an undo step.
+const STABLE_TIME: f64 = 0.5;
+
+/// Oldest undo steps are dropped beyond this many.
+const MAX_UNDOS: usize = 100;
+
+pub struct History<T> {
+    undos: Vec<T>,
+    redos: Vec<T>,
+    /// State after the latest recorded step.
+    recorded: T,
+    /// Time and value of the latest change not recorded yet.
+    pending: Option<(f64, T)>,
+}
+
+impl<T: Clone + PartialEq> History<T> {
+    pub fn new(state: &T) -> Self {
+        Self {
+            undos: Vec::new(),
+            redos: Vec::new(),
+            recorded: state.clone(),
+            pending: None,
+        }
+    }
+
+    /// Forgets every step, e.g. after opening a different exercise.
+    pub fn reset(&mut self, state: &T) {
+        *self = History::new(state);
+    }
+
+    /// Records `state` as an undo step once it has stopped changing.
+    pub fn track(&mut self, state: &T, now: f64) {
+        if *state == self.recorded {
+            self.pending = None;
+            return;
+        }
+        match &self.pending {
+            Some((changed_at, pending)) if pending == state => {
+                if now - changed_at >= STABLE_TIME {
+                    self.record(state);
+                }
+            }
+            _ => self.pending = Some((now, state.clone())),
+        }
+    }
+
+    pub fn can_undo(&self, state: &T) -> bool {
+        !self.undos.is_empty() || *state != self.recorded
+    }
+
+    pub fn can_redo(&self, state: &T) -> bool {
+        !self.redos.is_empty() && *state == self.recorded
+    }
+
+    /// The state before the latest step, if there is one.
+    pub fn undo(&mut self, state: &T) -> Option<T> {
+        self.record(state);
+        let previous = self.undos.pop()?;
+        let undone = std::mem::replace(&mut self.recorded, previous.clone());
+        self.redos.push(undone);
+        Some(previous)
+    }
+
+    /// The state the latest undo went back from. Editing after an undo
+    /// discards what could have been redone.
+    pub fn redo(&mut self, state: &T) -> Option<T> {
+        self.record(state);
+        let next = self.redos.pop()?;
+        let redone = std::mem::replace(&mut self.recorded, next.clone());
+        self.undos.push(redone);
+        Some(next)
+    }
+
+    fn record(&mut self, state: &T) {
+        self.pending = None;
+        if *state == self.recorded {
+            return;
+        }
+        let previous = std::mem::replace(&mut self.recorded, state.clone());
+        self.undos.push(previous);
+        if self.undos.len() > MAX_UNDOS {
+            self.undos.remove(0);
+        }
+        self.redos.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tracks `state` at `now` and again once it has been stable long enough.
+    fn settle(history: &mut History<String>, state: &str, now: f64) {
+        history.track(&state.to_string(), now);
+        history.track(&state.to_string(), now + STABLE_TIME);
+    }
+
+    #[test]
+    fn a_run_of_edits_becomes_one_step_once_stable() {
+        let mut history = History::new(&String::new());
+        for (i, state) in ["c", "cu", "cur", "curl"].into_iter().enumerate() {
+            history.track(&state.to_string(), i as f64 * 0.1);
+        }
+        history.track(&"curl".to_string(), 0.3 + STABLE_TIME / 2.0);
+        assert!(history.undos.is_empty());
+        history.track(&"curl".to_string(), 0.3 + STABLE_TIME);
+        assert_eq!(history.undos, [""]);
+
+        assert_eq!(history.undo(&"curl".to_string()).as_deref(), Some(""));
+        assert_eq!(history.undo(&String::new()), None);
+        assert_eq!(history.redo(&String::new()).as_deref(), Some("curl"));
+    }
+
+    #[test]
+    fn undo_in_the_middle_of_a_run_records_it_first() {
+        let mut history = History::new(&"curl".to_string());
+        history.track(&"curls".to_string(), 0.0);
+        assert!(history.can_undo(&"curls".to_string()));
+
+        assert_eq!(history.undo(&"curls".to_string()).as_deref(), Some("curl"));
+        assert!(history.can_redo(&"curl".to_string()));
+        assert_eq!(history.redo(&"curl".to_string()).as_deref(), Some("curls"));
+    }
+
+    #[test]
+    fn a_new_edit_clears_the_redo_stack() {
+        let mut history = History::new(&String::new());
+        settle(&mut history, "curl", 0.0);
+        settle(&mut history, "curl row", 1.0);
+        assert_eq!(history.undo(&"curl row".to_string()).as_deref(), Some("curl"));
+        assert!(history.can_redo(&"curl".to_string()));
+
+        // Editing after an undo is only redoable until it becomes a step
+        assert!(!history.can_redo(&"curl press".to_string()));
+        settle(&mut history, "curl press", 2.0);
+        assert!(!history.can_redo(&"curl press".to_string()));
+        assert_eq!(history.redo(&"curl press".to_string()), None);
+        assert_eq!(history.undo(&"curl press".to_string()).as_deref(), Some("curl"));
+    }
+
+    #[test]
+    fn oldest_steps_are_dropped_beyond_the_cap() {
+        let mut history = History::new(&"0".to_string());
+        for i in 1..=MAX_UNDOS + 5 {
+            settle(&mut history, &i.to_string(), i as f64);
+        }
+        assert_eq!(history.undos.len(), MAX_UNDOS);
+
+        let mut state = (MAX_UNDOS + 5).to_string();
+        while let Some(previous) = history.undo(&state) {
+            state = previous;
+        }
+        assert_eq!(state, "5");
+    }
+}
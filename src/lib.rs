@@ -0,0 +1,24 @@
+//! Data layer of the Hypertrophy Archive: the exercise models plus loading,
+//! saving, migrating, validating and searching exercise files, and the
+//! training programs and training log built from them.
+//!
+//! The desktop editor and command-line tool in `main.rs` are built on top of
+//! this crate; tooling that only needs the data can depend on it with
+//! `default-features = false` to leave out the GUI stack.
+
+pub mod analytics;
+pub mod biomechanics;
+pub mod coverage;
+pub mod library;
+pub mod migrate;
+pub mod models;
+pub mod muscle_length;
+pub mod program;
+pub mod query;
+pub mod resistance;
+pub mod schema;
+pub mod similarity;
+pub mod taxonomy;
+pub mod training_log;
+pub mod validation;
+pub mod volume;
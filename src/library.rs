@@ -0,0 +1,362 @@
+use crate::migrate::{self, MigrationError};
+use crate::models::{Exercise, ExerciseCollection, COLLECTION_SCHEMA_VERSION};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Reasons a single exercise file could not be read.
+#[derive(Debug)]
+pub enum StorageError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Migration(MigrationError),
+    /// Every exercise id up to `u32::MAX` is taken.
+    NoFreeId,
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::Io(e) => write!(f, "I/O error: {}", e),
+            StorageError::Json(e) => write!(f, "invalid JSON: {}", e),
+            StorageError::Migration(e) => write!(f, "{}", e),
+            StorageError::NoFreeId => write!(f, "no free exercise id left (id {} is in use)", u32::MAX),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+impl From<io::Error> for StorageError {
+    fn from(e: io::Error) -> Self {
+        StorageError::Io(e)
+    }
+}
+
+impl From<MigrationError> for StorageError {
+    fn from(e: MigrationError) -> Self {
+        StorageError::Migration(e)
+    }
+}
+
+impl From<serde_json::Error> for StorageError {
+    fn from(e: serde_json::Error) -> Self {
+        StorageError::Json(e)
+    }
+}
+
+/// An exercise that was successfully read from disk, together with its source file.
+#[derive(Debug)]
+pub struct LibraryEntry {
+    pub path: PathBuf,
+    pub exercise: Exercise,
+}
+
+/// A `*.json` file in the data directory that could not be turned into an `Exercise`.
+#[derive(Debug)]
+pub struct LoadFailure {
+    pub path: PathBuf,
+    pub error: StorageError,
+}
+
+/// Every exercise found in a data directory.
+#[derive(Debug, Default)]
+pub struct Library {
+    pub entries: Vec<LibraryEntry>,
+    pub failures: Vec<LoadFailure>,
+}
+
+impl Library {
+    /// Scans `dir` (non-recursively) and deserializes every `*.json` file in it.
+    ///
+    /// A missing directory is treated as an empty library. Files that fail to
+    /// parse are collected in `failures` instead of aborting the scan.
+    pub fn load(dir: &Path) -> io::Result<Library> {
+        let mut library = Library::default();
+
+        for path in json_files(dir)? {
+            match read_exercise(&path) {
+                Ok(exercise) => library.entries.push(LibraryEntry { path, exercise }),
+                Err(error) => library.failures.push(LoadFailure { path, error }),
+            }
+        }
+
+        Ok(library)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn find_by_id(&self, id: u32) -> Option<&LibraryEntry> {
+        self.entries.iter().find(|entry| entry.exercise.id == id)
+    }
+
+    /// Case-insensitive match on `Exercise.name`.
+    pub fn find_by_name(&self, name: &str) -> Option<&LibraryEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.exercise.name.eq_ignore_ascii_case(name))
+    }
+
+    /// The smallest id greater than every id currently in the library.
+    pub fn next_free_id(&self) -> Result<u32, StorageError> {
+        match self.entries.iter().map(|entry| entry.exercise.id).max() {
+            Some(max) => max.checked_add(1).ok_or(StorageError::NoFreeId),
+            None => Ok(0),
+        }
+    }
+
+    /// Picks the id an exercise should be saved with at `path`.
+    ///
+    /// The exercise keeps `id` unless another file already uses it, in which
+    /// case it gets the next free id.
+    pub fn assign_id(&self, id: u32, path: &Path) -> Result<u32, StorageError> {
+        let taken = self
+            .entries
+            .iter()
+            .any(|entry| entry.exercise.id == id && entry.path != path);
+        if taken { self.next_free_id() } else { Ok(id) }
+    }
+
+    /// Ids shared by more than one file, with the files that share them.
+    pub fn duplicate_ids(&self) -> BTreeMap<u32, Vec<&Path>> {
+        let mut by_id: BTreeMap<u32, Vec<&Path>> = BTreeMap::new();
+        for entry in &self.entries {
+            by_id.entry(entry.exercise.id).or_default().push(&entry.path);
+        }
+        by_id.retain(|_, paths| paths.len() > 1);
+        by_id
+    }
+
+    /// Gives every file that shares an id with an earlier file (in path order)
+    /// a fresh id and writes it back to disk.
+    ///
+    /// Returns the files that were changed with their old and new ids.
+    pub fn renumber_duplicates(&mut self) -> Result<Vec<Renumbered>, StorageError> {
+        let mut next_id = self.next_free_id().ok();
+        let mut seen = HashSet::new();
+        let mut changes = Vec::new();
+
+        for entry in &mut self.entries {
+            if seen.insert(entry.exercise.id) {
+                continue;
+            }
+            let old_id = entry.exercise.id;
+            let new_id = next_id.ok_or(StorageError::NoFreeId)?;
+            entry.exercise.id = new_id;
+            write_exercise(&entry.path, &entry.exercise)?;
+            changes.push(Renumbered {
+                path: entry.path.clone(),
+                old_id,
+                new_id,
+            });
+            next_id = new_id.checked_add(1);
+        }
+
+        Ok(changes)
+    }
+}
+
+/// A file whose id was changed by [`Library::renumber_duplicates`].
+#[derive(Debug)]
+pub struct Renumbered {
+    pub path: PathBuf,
+    pub old_id: u32,
+    pub new_id: u32,
+}
+
+/// Turns a user-supplied name into the file stem used inside the data directory.
+pub fn sanitize_filename(name: &str) -> String {
+    // Path separators and characters Windows forbids would escape the folder or fail to save
+    let sanitized: String = name
+        .trim()
+        .chars()
+        .map(|c| if c == ' ' || "<>:\"/\\|?*".contains(c) || c.is_control() { '_' } else { c })
+        .collect::<String>()
+        .to_lowercase();
+    let sanitized = sanitized.trim_matches('.');
+    if sanitized.is_empty() { "unnamed_exercise".to_string() } else { sanitized.to_string() }
+}
+
+/// Reads a single exercise file, upgrading it from older schema versions if needed.
+pub fn read_exercise(path: &Path) -> Result<Exercise, StorageError> {
+    let contents = fs::read_to_string(path)?;
+    Ok(migrate::parse_exercise(&contents)?)
+}
+
+/// Serializes an exercise as pretty-printed JSON and writes it to `path`.
+pub fn write_exercise(path: &Path, exercise: &Exercise) -> Result<(), StorageError> {
+    let json = serde_json::to_string_pretty(exercise)?;
+    write_atomic(path, json.as_bytes())?;
+    Ok(())
+}
+
+/// The `*.json` files directly inside `dir`, sorted by path. A missing
+/// directory has none.
+pub fn json_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "json") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Writes `contents` to a temporary file next to `path` and renames it over
+/// `path`, so a crash or a concurrent reader never sees a half-written file.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = (|| {
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Copies the current contents of `path` to `<path>.bak`, replacing an older
+/// backup. Returns the backup path, or `None` if there was nothing to back up.
+pub fn backup_file(path: &Path) -> io::Result<Option<PathBuf>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let mut backup_name = path.file_name().unwrap_or_default().to_os_string();
+    backup_name.push(".bak");
+    let backup_path = path.with_file_name(backup_name);
+    fs::copy(path, &backup_path)?;
+    Ok(Some(backup_path))
+}
+
+impl ExerciseCollection {
+    /// Bundles every exercise in the library, ordered by id.
+    pub fn from_library(library: &Library) -> Self {
+        let mut exercises: Vec<Exercise> = library.entries.iter().map(|e| e.exercise.clone()).collect();
+        exercises.sort_by_key(|e| e.id);
+        ExerciseCollection::new(exercises)
+    }
+
+    /// Loads a collection file; exercises inside it are migrated individually.
+    pub fn load(path: &Path) -> Result<Self, StorageError> {
+        let contents = fs::read_to_string(path)?;
+        let mut value = migrate::parse_lenient(&contents)?;
+        if let Some(version) = value.get("schema_version").and_then(Value::as_u64)
+            && version > u64::from(COLLECTION_SCHEMA_VERSION)
+        {
+            let version = u32::try_from(version).unwrap_or(u32::MAX);
+            return Err(MigrationError::UnsupportedCollectionVersion(version).into());
+        }
+        if let Some(Value::Array(exercises)) = value.get_mut("exercises") {
+            for exercise in exercises.iter_mut() {
+                *exercise = migrate::upgrade(exercise.take())?;
+            }
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), StorageError> {
+        let json = serde_json::to_string_pretty(self)?;
+        write_atomic(path, json.as_bytes())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty directory for one test, under the system temp directory.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("hypertrophy_archive_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn entry(path: &str, id: u32) -> LibraryEntry {
+        LibraryEntry {
+            path: PathBuf::from(path),
+            exercise: Exercise { id, name: path.to_string(), ..Exercise::default() },
+        }
+    }
+
+    fn library(entries: Vec<LibraryEntry>) -> Library {
+        Library { entries, ..Library::default() }
+    }
+
+    #[test]
+    fn next_free_id_follows_the_highest_id() {
+        assert_eq!(library(vec![]).next_free_id().unwrap(), 0);
+        assert_eq!(library(vec![entry("a.json", 5), entry("b.json", 0)]).next_free_id().unwrap(), 6);
+        assert!(matches!(
+            library(vec![entry("a.json", u32::MAX)]).next_free_id(),
+            Err(StorageError::NoFreeId)
+        ));
+    }
+
+    #[test]
+    fn assign_id_keeps_ids_that_are_free_or_already_this_files() {
+        let library = library(vec![entry("a.json", 0), entry("b.json", 3)]);
+        assert_eq!(library.assign_id(3, Path::new("b.json")).unwrap(), 3);
+        assert_eq!(library.assign_id(3, Path::new("c.json")).unwrap(), 4);
+        assert_eq!(library.assign_id(2, Path::new("c.json")).unwrap(), 2);
+    }
+
+    #[test]
+    fn renumber_duplicates_rewrites_later_files() {
+        let dir = temp_dir("renumber");
+        for (name, id) in [("a", 1), ("b", 1), ("c", 3)] {
+            let exercise = Exercise { id, name: name.to_string(), ..Exercise::default() };
+            write_exercise(&dir.join(format!("{}.json", name)), &exercise).unwrap();
+        }
+
+        let mut library = Library::load(&dir).unwrap();
+        assert_eq!(library.duplicate_ids().keys().collect::<Vec<_>>(), [&1]);
+        let changes = library.renumber_duplicates().unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!((changes[0].path.clone(), changes[0].old_id, changes[0].new_id), (dir.join("b.json"), 1, 4));
+
+        let reloaded = Library::load(&dir).unwrap();
+        let ids: Vec<u32> = reloaded.entries.iter().map(|e| e.exercise.id).collect();
+        assert_eq!(ids, [1, 4, 3]);
+        assert!(reloaded.duplicate_ids().is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn collections_from_a_newer_version_are_rejected() {
+        let dir = temp_dir("collection_version");
+        let path = dir.join("archive.json");
+        ExerciseCollection::new(vec![Exercise::default()]).save(&path).unwrap();
+        assert_eq!(ExerciseCollection::load(&path).unwrap().exercises.len(), 1);
+
+        let mut value: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        value["schema_version"] = Value::from(COLLECTION_SCHEMA_VERSION + 1);
+        fs::write(&path, value.to_string()).unwrap();
+        assert!(matches!(
+            ExerciseCollection::load(&path),
+            Err(StorageError::Migration(MigrationError::UnsupportedCollectionVersion(_)))
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,20 +1,32 @@
-mod models;
+mod cli;
+#[cfg(feature = "gui")]
 mod gui;
+#[cfg(feature = "gui")]
+mod history;
 
-use models::*;
-use std::fs::{self, File};
-use std::io::{self, Read, Write};
-use std::path::Path;
-
+#[cfg(feature = "gui")]
 fn main() -> Result<(), eframe::Error> {
+    // Any command-line arguments switch to the headless CLI
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(cli::run(&args));
+    }
+
     let options = eframe::NativeOptions {
-        initial_window_size: Some(egui::vec2(800.0, 600.0)),
+        initial_window_size: Some(egui::vec2(900.0, 600.0)),
         ..Default::default()
     };
-    
+
     eframe::run_native(
         "Hypertrophy Archive",
         options,
         Box::new(|_cc| Box::new(gui::HypertrophyApp::default())),
     )
 }
+
+// Built without the `gui` feature there is no window to fall back to
+#[cfg(not(feature = "gui"))]
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    std::process::exit(cli::run(&args));
+}
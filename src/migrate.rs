@@ -0,0 +1,498 @@
+//! Upgrades older exercise documents to the current `Exercise` layout.
+//!
+//! Documents are first parsed into a `serde_json::Value` (keeping keys that
+//! appear more than once, as in the README's example entry), then run through
+//! one migration step per schema version until they reach
+//! `CURRENT_SCHEMA_VERSION`, and only then deserialized into `Exercise`.
+
+use crate::models::{Exercise, JointAction, COLLECTION_SCHEMA_VERSION, CURRENT_SCHEMA_VERSION};
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde_json::{json, Map, Value};
+use std::collections::HashSet;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum MigrationError {
+    Json(serde_json::Error),
+    /// The document claims a version newer than this build understands.
+    UnsupportedVersion(u32),
+    /// A collection file from a newer build than this one.
+    UnsupportedCollectionVersion(u32),
+    Malformed(String),
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationError::Json(e) => write!(f, "invalid JSON: {}", e),
+            MigrationError::UnsupportedVersion(v) => write!(
+                f,
+                "schema version {} is newer than the supported version {}",
+                v, CURRENT_SCHEMA_VERSION
+            ),
+            MigrationError::UnsupportedCollectionVersion(v) => write!(
+                f,
+                "collection schema version {} is newer than the supported version {}",
+                v, COLLECTION_SCHEMA_VERSION
+            ),
+            MigrationError::Malformed(msg) => write!(f, "malformed exercise: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+impl From<serde_json::Error> for MigrationError {
+    fn from(e: serde_json::Error) -> Self {
+        MigrationError::Json(e)
+    }
+}
+
+type Migration = fn(&mut Map<String, Value>) -> Result<(), MigrationError>;
+
+/// Migration steps, where `MIGRATIONS[n]` upgrades a version `n` document to `n + 1`.
+const MIGRATIONS: &[Migration] = &[v0_to_v1, v1_to_v2, v2_to_v3];
+
+/// Parses an exercise document of any supported version.
+pub fn parse_exercise(json: &str) -> Result<Exercise, MigrationError> {
+    let value = parse_lenient(json)?;
+    exercise_from_value(value)
+}
+
+/// Upgrades an already-parsed document and deserializes it.
+pub fn exercise_from_value(value: Value) -> Result<Exercise, MigrationError> {
+    let upgraded = upgrade(value)?;
+    Ok(serde_json::from_value(upgraded)?)
+}
+
+/// Parses JSON like `serde_json::from_str`, except that repeated keys in an
+/// object are collected into an array instead of the last one winning.
+pub fn parse_lenient(json: &str) -> Result<Value, MigrationError> {
+    let LenientValue(value) = serde_json::from_str(json)?;
+    Ok(value)
+}
+
+/// Runs every migration step needed to bring `value` to `CURRENT_SCHEMA_VERSION`.
+pub fn upgrade(mut value: Value) -> Result<Value, MigrationError> {
+    let object = value
+        .as_object_mut()
+        .ok_or_else(|| MigrationError::Malformed("expected a JSON object".to_string()))?;
+
+    let mut version = schema_version(object)?;
+    if version > CURRENT_SCHEMA_VERSION {
+        return Err(MigrationError::UnsupportedVersion(version));
+    }
+
+    while version < CURRENT_SCHEMA_VERSION {
+        MIGRATIONS[version as usize](object)?;
+        version += 1;
+        object.insert("schema_version".to_string(), Value::from(version));
+    }
+
+    Ok(value)
+}
+
+/// Documents written before versioning have no `schema_version` and count as version 0.
+fn schema_version(object: &Map<String, Value>) -> Result<u32, MigrationError> {
+    match object.get("schema_version") {
+        None => Ok(0),
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| MigrationError::Malformed(format!("invalid schema_version {}", v))),
+    }
+}
+
+/// Version 0 allowed a single `muscle_division` or `joint` object where a
+/// list is now expected, which is what the README's duplicate-key form
+/// becomes when only one key is present.
+fn v0_to_v1(object: &mut Map<String, Value>) -> Result<(), MigrationError> {
+    if let Some(Value::Object(target)) = object.get_mut("target_muscles") {
+        wrap_in_array(target, "muscle_division");
+    }
+    if let Some(Value::Object(joints)) = object.get_mut("joints_involved") {
+        wrap_in_array(joints, "joint");
+    }
+    Ok(())
+}
+
+/// Version 2 replaced the single `muscle_name` with a list of muscles that
+/// each have a role, and the `active` flag on divisions with an emphasis level.
+/// The old muscle becomes the primary one; active divisions get high emphasis.
+fn v1_to_v2(object: &mut Map<String, Value>) -> Result<(), MigrationError> {
+    let Some(Value::Object(target)) = object.get_mut("target_muscles") else {
+        return Ok(());
+    };
+
+    let muscle_name = target.remove("muscle_name").unwrap_or(Value::String(String::new()));
+    let divisions = match target.remove("muscle_division") {
+        Some(Value::Array(divisions)) => divisions,
+        Some(other) => {
+            return Err(MigrationError::Malformed(format!(
+                "expected a list of muscle divisions, found {}",
+                other
+            )));
+        }
+        None => Vec::new(),
+    };
+
+    let divisions: Vec<Value> = divisions
+        .into_iter()
+        .map(|mut division| {
+            if let Value::Object(fields) = &mut division {
+                let active = fields.remove("active").and_then(|a| a.as_bool()).unwrap_or(false);
+                fields.insert(
+                    "emphasis".to_string(),
+                    Value::from(if active { "high" } else { "none" }),
+                );
+            }
+            division
+        })
+        .collect();
+
+    let has_muscle = muscle_name.as_str().is_some_and(|name| !name.is_empty());
+    let muscles = if has_muscle {
+        vec![json!({
+            "muscle_name": muscle_name,
+            "role": "primary",
+            "muscle_division": divisions,
+        })]
+    } else {
+        Vec::new()
+    };
+    target.insert("muscle".to_string(), Value::Array(muscles));
+    Ok(())
+}
+
+/// Version 3 replaced the free-text joint `direction` with a `JointAction`.
+/// Common spellings are mapped onto an action; anything unrecognised is
+/// moved to `unmapped_direction`, which validation reports.
+fn v2_to_v3(object: &mut Map<String, Value>) -> Result<(), MigrationError> {
+    let Some(Value::Object(joints)) = object.get_mut("joints_involved") else {
+        return Ok(());
+    };
+    let Some(Value::Array(joints)) = joints.get_mut("joint") else {
+        return Ok(());
+    };
+
+    for joint in joints.iter_mut().filter_map(Value::as_object_mut) {
+        let text = match joint.remove("direction") {
+            None | Some(Value::Null) => continue,
+            Some(Value::String(text)) => text,
+            Some(other) => other.to_string(),
+        };
+        if let Some(action) = legacy_joint_action(&text) {
+            joint.insert("direction".to_string(), serde_json::to_value(action)?);
+        } else if !text.trim().is_empty() {
+            joint.insert("unmapped_direction".to_string(), Value::String(text));
+        }
+    }
+    Ok(())
+}
+
+fn legacy_joint_action(direction: &str) -> Option<JointAction> {
+    let action = match direction.trim().to_lowercase().as_str() {
+        "flex" | "flexing" | "curl" | "bend" => JointAction::Flexion,
+        "extend" | "extending" | "straighten" | "lockout" => JointAction::Extension,
+        "abduct" | "raise" => JointAction::Abduction,
+        "adduct" => JointAction::Adduction,
+        "horizontal abduct" | "fly reverse" | "reverse fly" => JointAction::HorizontalAbduction,
+        "horizontal adduct" | "fly" | "press" => JointAction::HorizontalAdduction,
+        "internal" | "internal rotate" => JointAction::InternalRotation,
+        "external" | "external rotate" => JointAction::ExternalRotation,
+        "shrug" | "elevate" => JointAction::Elevation,
+        "depress" => JointAction::Depression,
+        "protract" => JointAction::Protraction,
+        "retract" => JointAction::Retraction,
+        "pronate" => JointAction::Pronation,
+        "supinate" => JointAction::Supination,
+        "twist" | "rotate" => JointAction::Rotation,
+        "plantar flex" | "plantarflex" => JointAction::PlantarFlexion,
+        "dorsiflex" => JointAction::Dorsiflexion,
+        other => return other.parse().ok(),
+    };
+    Some(action)
+}
+
+fn wrap_in_array(object: &mut Map<String, Value>, key: &str) {
+    match object.get_mut(key) {
+        Some(value @ Value::Object(_)) => *value = Value::Array(vec![value.take()]),
+        Some(_) => {}
+        None => {
+            object.insert(key.to_string(), Value::Array(Vec::new()));
+        }
+    }
+}
+
+/// A `serde_json::Value` that merges duplicate object keys into arrays.
+struct LenientValue(Value);
+
+impl<'de> Deserialize<'de> for LenientValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(LenientVisitor)
+    }
+}
+
+struct LenientVisitor;
+
+impl<'de> Visitor<'de> for LenientVisitor {
+    type Value = LenientValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "any JSON value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(LenientValue(Value::Bool(v)))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(LenientValue(Value::from(v)))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(LenientValue(Value::from(v)))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(LenientValue(Value::from(v)))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(LenientValue(Value::String(v.to_string())))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+        Ok(LenientValue(Value::String(v)))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(LenientValue(Value::Null))
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(LenientValue(Value::Null))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut items = Vec::new();
+        while let Some(LenientValue(item)) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(LenientValue(Value::Array(items)))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+        let mut map = Map::new();
+        let mut merged = HashSet::new();
+        while let Some((key, LenientValue(value))) = access.next_entry::<String, LenientValue>()? {
+            match map.get_mut(&key) {
+                Some(Value::Array(items)) if merged.contains(&key) => items.push(value),
+                Some(existing) => {
+                    *existing = Value::Array(vec![existing.take(), value]);
+                    merged.insert(key);
+                }
+                None => {
+                    map.insert(key, value);
+                }
+            }
+        }
+        Ok(LenientValue(Value::Object(map)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::*;
+
+    /// The example entry from the README before versioning, with repeated keys.
+    const README_LEGACY: &str = r#"{
+        "name": "chest_press",
+        "id": 0,
+        "type": "compound",
+        "description": "A press in the transverse plane.",
+        "target_muscles": {
+            "muscle_name": "chest",
+            "muscle_division": { "name": "sternocostal", "active": true },
+            "muscle_division": { "name": "clavicular", "active": true },
+            "muscle_division": { "name": "abdominal", "active": true }
+        },
+        "joints_involved": {
+            "joint": { "name": "shoulder", "dynamic": false, "angle": 45 },
+            "joint": {
+                "name": "elbow",
+                "dynamic": true,
+                "direction": "extension",
+                "angle_initial": 30,
+                "angle_final": 180
+            }
+        },
+        "resistance_profile": "descending",
+        "plane_of_motion": "transverse",
+        "tips": "Keep those shoulders tucked!",
+        "technique_video": "https://youtube.com/shorts/hWbUlkb5Ms4?si=P89i2PXyGlX_q7XE"
+    }"#;
+
+    /// What the original `save_exercise` wrote: lists, no version, free-text directions.
+    const BASELINE_SAVE: &str = r#"{
+        "name": "cable curl",
+        "id": 0,
+        "type": "isolation",
+        "description": "",
+        "target_muscles": {
+            "muscle_name": "biceps",
+            "muscle_division": [
+                { "name": "short head", "active": true },
+                { "name": "long head", "active": false }
+            ]
+        },
+        "joints_involved": {
+            "joint": [
+                { "name": "elbow", "dynamic": true, "direction": "Curl", "angle_initial": 180, "angle_final": 40 },
+                { "name": "wrist", "dynamic": true, "direction": "sideways", "angle_initial": 0, "angle_final": 10 }
+            ]
+        },
+        "resistance_profile": "bell",
+        "plane_of_motion": "sagittal",
+        "tips": "",
+        "technique_video": ""
+    }"#;
+
+    #[test]
+    fn lenient_parse_merges_repeated_keys() {
+        let value = parse_lenient(r#"{ "a": 1, "a": 2, "a": 3, "b": [1], "c": [1], "c": [2] }"#).unwrap();
+        assert_eq!(value, json!({ "a": [1, 2, 3], "b": [1], "c": [[1], [2]] }));
+    }
+
+    #[test]
+    fn readme_legacy_example_migrates_to_current_version() {
+        let exercise = parse_exercise(README_LEGACY).unwrap();
+        assert_eq!(exercise.schema_version, CURRENT_SCHEMA_VERSION);
+        assert_eq!(exercise.name, "chest_press");
+        assert_eq!(exercise.r#type, ExerciseType::Compound);
+
+        let [chest] = exercise.target_muscles.muscles.as_slice() else {
+            panic!("expected one muscle, got {:?}", exercise.target_muscles.muscles);
+        };
+        assert_eq!(chest.muscle_name, "chest");
+        assert_eq!(chest.role, MuscleRole::Primary);
+        let divisions: Vec<(&str, Emphasis)> =
+            chest.muscle_divisions.iter().map(|d| (d.name.as_str(), d.emphasis)).collect();
+        assert_eq!(
+            divisions,
+            [("sternocostal", Emphasis::High), ("clavicular", Emphasis::High), ("abdominal", Emphasis::High)]
+        );
+
+        let [shoulder, elbow] = exercise.joints_involved.joints.as_slice() else {
+            panic!("expected two joints, got {:?}", exercise.joints_involved.joints);
+        };
+        assert_eq!((shoulder.name.as_str(), shoulder.dynamic, shoulder.angle), ("shoulder", false, Some(45)));
+        assert_eq!(shoulder.direction, None);
+        assert_eq!(elbow.direction, Some(JointAction::Extension));
+        assert_eq!((elbow.angle_initial, elbow.angle_final), (Some(30), Some(180)));
+        assert_eq!(exercise.resistance_profile, ResistanceProfile::Descending);
+        assert_eq!(exercise.plane_of_motion, PlaneOfMotion::Transverse);
+    }
+
+    #[test]
+    fn baseline_save_output_migrates_to_current_version() {
+        let exercise = parse_exercise(BASELINE_SAVE).unwrap();
+        assert_eq!(exercise.schema_version, CURRENT_SCHEMA_VERSION);
+
+        let biceps = &exercise.target_muscles.muscles[0];
+        assert_eq!(biceps.muscle_name, "biceps");
+        let emphasis: Vec<Emphasis> = biceps.muscle_divisions.iter().map(|d| d.emphasis).collect();
+        assert_eq!(emphasis, [Emphasis::High, Emphasis::None]);
+
+        // "Curl" is a legacy spelling of flexion; an unknown direction is kept aside
+        let joints = &exercise.joints_involved.joints;
+        assert_eq!(joints[0].direction, Some(JointAction::Flexion));
+        assert_eq!(joints[0].unmapped_direction, None);
+        assert_eq!(joints[1].direction, None);
+        assert_eq!(joints[1].unmapped_direction.as_deref(), Some("sideways"));
+        assert_eq!(joints[1].angle_final, Some(10));
+    }
+
+    #[test]
+    fn chain_upgrades_one_version_at_a_time() {
+        let mut value = parse_lenient(README_LEGACY).unwrap();
+        let object = value.as_object_mut().unwrap();
+        assert_eq!(schema_version(object).unwrap(), 0);
+        assert_eq!(MIGRATIONS.len() as u32, CURRENT_SCHEMA_VERSION);
+
+        // v1: repeated keys are lists, still one muscle_name and active flags
+        MIGRATIONS[0](object).unwrap();
+        assert_eq!(object["target_muscles"]["muscle_division"][2], json!({ "name": "abdominal", "active": true }));
+        assert_eq!(object["target_muscles"]["muscle_name"], "chest");
+
+        // v2: a list of muscles with roles, divisions with emphasis
+        MIGRATIONS[1](object).unwrap();
+        assert_eq!(object["target_muscles"]["muscle"][0]["role"], "primary");
+        assert_eq!(object["target_muscles"]["muscle"][0]["muscle_division"][0]["emphasis"], "high");
+        assert!(object["target_muscles"].get("muscle_name").is_none());
+
+        // v3: directions are joint actions
+        MIGRATIONS[2](object).unwrap();
+        assert_eq!(object["joints_involved"]["joint"][1]["direction"], "extension");
+        assert!(object["joints_involved"]["joint"][0].get("direction").is_none());
+    }
+
+    #[test]
+    fn current_documents_are_left_alone() {
+        let exercise = parse_exercise(BASELINE_SAVE).unwrap();
+        let value = serde_json::to_value(&exercise).unwrap();
+        assert_eq!(upgrade(value.clone()).unwrap(), value);
+    }
+
+    #[test]
+    fn single_object_where_a_list_is_expected_is_wrapped() {
+        let exercise = parse_exercise(
+            r#"{
+                "schema_version": 0, "name": "fly", "id": 1, "type": "isolation", "description": "",
+                "target_muscles": { "muscle_name": "chest", "muscle_division": { "name": "sternocostal", "active": true } },
+                "joints_involved": { "joint": { "name": "shoulder", "dynamic": true, "direction": "fly" } },
+                "resistance_profile": "bell", "plane_of_motion": "transverse", "tips": "", "technique_video": ""
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(exercise.target_muscles.muscles[0].muscle_divisions.len(), 1);
+        assert_eq!(exercise.joints_involved.joints[0].direction, Some(JointAction::HorizontalAdduction));
+    }
+
+    #[test]
+    fn unmapped_directions_are_kept_and_reported() {
+        let exercise = parse_exercise(BASELINE_SAVE).unwrap();
+        let issue = exercise
+            .validate()
+            .issues
+            .into_iter()
+            .find(|issue| issue.path == "joints_involved.joint[1].direction")
+            .expect("the wrist has no direction");
+        assert_eq!(issue.kind, crate::validation::IssueKind::UnmappedDirection("sideways".to_string()));
+
+        let saved = serde_json::to_value(&exercise).unwrap();
+        assert_eq!(saved["joints_involved"]["joint"][1]["unmapped_direction"], "sideways");
+
+        // Blank and null directions carry nothing worth keeping
+        let mut object = json!({ "joints_involved": { "joint": [{ "direction": "  " }, { "direction": null }] } });
+        v2_to_v3(object.as_object_mut().unwrap()).unwrap();
+        assert_eq!(object["joints_involved"]["joint"], json!([{}, {}]));
+    }
+
+    #[test]
+    fn legacy_directions_map_onto_actions() {
+        assert_eq!(legacy_joint_action(" Flex "), Some(JointAction::Flexion));
+        assert_eq!(legacy_joint_action("reverse fly"), Some(JointAction::HorizontalAbduction));
+        assert_eq!(legacy_joint_action("plantar_flexion"), Some(JointAction::PlantarFlexion));
+        assert_eq!(legacy_joint_action("wiggle"), None);
+    }
+
+    #[test]
+    fn newer_and_invalid_versions_are_rejected() {
+        let newer = json!({ "schema_version": CURRENT_SCHEMA_VERSION + 1 });
+        assert!(matches!(upgrade(newer), Err(MigrationError::UnsupportedVersion(v)) if v == CURRENT_SCHEMA_VERSION + 1));
+        assert!(matches!(upgrade(json!({ "schema_version": "two" })), Err(MigrationError::Malformed(_))));
+        assert!(matches!(upgrade(json!([])), Err(MigrationError::Malformed(_))));
+    }
+}
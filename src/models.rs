@@ -1,7 +1,18 @@
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Version written to the `schema_version` field of every exercise file.
+///
+/// Bump this together with a new step in `migrate` whenever the serialized
+/// shape of `Exercise` changes.
+pub const CURRENT_SCHEMA_VERSION: u32 = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct Exercise {
+    pub schema_version: u32,
     pub name: String,
     pub id: u32,
     pub r#type: ExerciseType,
@@ -12,61 +23,415 @@ pub struct Exercise {
     pub plane_of_motion: PlaneOfMotion,
     pub tips: String,
     pub technique_video: String,
+    /// Concrete implementations of this archetype.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<EquipmentVariant>,
+}
+
+impl Default for Exercise {
+    fn default() -> Self {
+        Self {
+            schema_version: CURRENT_SCHEMA_VERSION,
+            name: String::new(),
+            id: 0,
+            r#type: ExerciseType::default(),
+            description: String::new(),
+            target_muscles: TargetMuscles::default(),
+            joints_involved: JointsInvolved::default(),
+            resistance_profile: ResistanceProfile::default(),
+            plane_of_motion: PlaneOfMotion::default(),
+            tips: String::new(),
+            technique_video: String::new(),
+            variants: Vec::new(),
+        }
+    }
+}
+
+/// One way to perform an exercise with a particular implement, and the
+/// resistance profile that implement produces.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct EquipmentVariant {
+    pub equipment: Equipment,
+    /// A more specific name, e.g. "EZ bar" or "plate-loaded".
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    pub resistance_profile: ResistanceProfile,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+}
+
+impl EquipmentVariant {
+    /// The specific name if there is one, otherwise the equipment.
+    pub fn label(&self) -> String {
+        if self.name.trim().is_empty() {
+            self.equipment.label().to_string()
+        } else {
+            format!("{} ({})", self.name, self.equipment.label())
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Equipment {
+    #[default]
+    Dumbbell,
+    Barbell,
+    Cable,
+    Machine,
+    Band,
+    Bodyweight,
+}
+
+impl Equipment {
+    pub const ALL: [Equipment; 6] = [
+        Equipment::Dumbbell,
+        Equipment::Barbell,
+        Equipment::Cable,
+        Equipment::Machine,
+        Equipment::Band,
+        Equipment::Bodyweight,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Equipment::Dumbbell => "dumbbell",
+            Equipment::Barbell => "barbell",
+            Equipment::Cable => "cable",
+            Equipment::Machine => "machine",
+            Equipment::Band => "band",
+            Equipment::Bodyweight => "bodyweight",
+        }
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema, PartialEq, Clone)]
 #[serde(rename_all = "lowercase")]
 pub enum ExerciseType {
+    #[default]
     Compound,
     Isolation,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct TargetMuscles {
+    #[serde(rename = "muscle")]
+    pub muscles: Vec<TargetMuscle>,
+}
+
+impl TargetMuscles {
+    /// The first muscle with the primary role.
+    pub fn primary(&self) -> Option<&TargetMuscle> {
+        self.muscles.iter().find(|m| m.role == MuscleRole::Primary)
+    }
+
+    /// Name of the primary muscle, or an empty string if there is none.
+    pub fn primary_name(&self) -> &str {
+        self.primary().map_or("", |m| m.muscle_name.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct TargetMuscle {
     pub muscle_name: String,
+    pub role: MuscleRole,
     #[serde(rename = "muscle_division")]
     pub muscle_divisions: Vec<MuscleDivision>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl TargetMuscle {
+    pub fn new(muscle_name: String, role: MuscleRole) -> Self {
+        Self {
+            muscle_name,
+            role,
+            muscle_divisions: Vec::new(),
+        }
+    }
+
+    /// Emphasis of the named division; divisions that are not listed have none.
+    pub fn emphasis(&self, division: &str) -> Emphasis {
+        self.muscle_divisions
+            .iter()
+            .find(|d| d.name == division)
+            .map_or(Emphasis::None, |d| d.emphasis)
+    }
+
+    pub fn active_divisions(&self) -> impl Iterator<Item = &MuscleDivision> {
+        self.muscle_divisions.iter().filter(|d| d.is_active())
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum MuscleRole {
+    #[default]
+    Primary,
+    Secondary,
+    Stabilizer,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct MuscleDivision {
     pub name: String,
-    pub active: bool,
+    pub emphasis: Emphasis,
+}
+
+impl MuscleDivision {
+    pub fn is_active(&self) -> bool {
+        self.emphasis != Emphasis::None
+    }
+}
+
+/// How strongly an exercise loads a muscle division, from none to high.
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Emphasis {
+    #[default]
+    None,
+    Low,
+    Moderate,
+    High,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct JointsInvolved {
     #[serde(rename = "joint")]
     pub joints: Vec<Joint>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct Joint {
     pub name: String,
     pub dynamic: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(range(max = 180))]
     pub angle: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub direction: Option<String>,
+    pub direction: Option<JointAction>,
+    /// Free-text direction from an older file that matched no joint action,
+    /// kept until a direction is picked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unmapped_direction: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(range(max = 180))]
     pub angle_initial: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(range(max = 180))]
     pub angle_final: Option<u32>,
+    /// Measured or modelled torque across the range of a dynamic joint.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resistance_curve: Option<ResistanceCurve>,
+}
+
+/// External torque on a joint sampled at points in its range of motion.
+/// Torque can be in any unit as long as one curve uses it consistently.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct ResistanceCurve {
+    #[serde(rename = "sample")]
+    pub samples: Vec<TorqueSample>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct TorqueSample {
+    #[schemars(range(max = 180))]
+    pub angle: u32,
+    pub torque: f64,
+}
+
+/// The movement a dynamic joint performs. Which actions are legal for which
+/// joint is listed in the taxonomy.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum JointAction {
+    Flexion,
+    Extension,
+    Abduction,
+    Adduction,
+    HorizontalAbduction,
+    HorizontalAdduction,
+    InternalRotation,
+    ExternalRotation,
+    Elevation,
+    Depression,
+    Protraction,
+    Retraction,
+    UpwardRotation,
+    DownwardRotation,
+    Pronation,
+    Supination,
+    RadialDeviation,
+    UlnarDeviation,
+    LateralFlexion,
+    Rotation,
+    PlantarFlexion,
+    Dorsiflexion,
+    Inversion,
+    Eversion,
+}
+
+impl JointAction {
+    pub const ALL: [JointAction; 24] = [
+        JointAction::Flexion,
+        JointAction::Extension,
+        JointAction::Abduction,
+        JointAction::Adduction,
+        JointAction::HorizontalAbduction,
+        JointAction::HorizontalAdduction,
+        JointAction::InternalRotation,
+        JointAction::ExternalRotation,
+        JointAction::Elevation,
+        JointAction::Depression,
+        JointAction::Protraction,
+        JointAction::Retraction,
+        JointAction::UpwardRotation,
+        JointAction::DownwardRotation,
+        JointAction::Pronation,
+        JointAction::Supination,
+        JointAction::RadialDeviation,
+        JointAction::UlnarDeviation,
+        JointAction::LateralFlexion,
+        JointAction::Rotation,
+        JointAction::PlantarFlexion,
+        JointAction::Dorsiflexion,
+        JointAction::Inversion,
+        JointAction::Eversion,
+    ];
+
+    /// Human-readable name, e.g. "horizontal adduction".
+    pub fn label(self) -> &'static str {
+        match self {
+            JointAction::Flexion => "flexion",
+            JointAction::Extension => "extension",
+            JointAction::Abduction => "abduction",
+            JointAction::Adduction => "adduction",
+            JointAction::HorizontalAbduction => "horizontal abduction",
+            JointAction::HorizontalAdduction => "horizontal adduction",
+            JointAction::InternalRotation => "internal rotation",
+            JointAction::ExternalRotation => "external rotation",
+            JointAction::Elevation => "elevation",
+            JointAction::Depression => "depression",
+            JointAction::Protraction => "protraction",
+            JointAction::Retraction => "retraction",
+            JointAction::UpwardRotation => "upward rotation",
+            JointAction::DownwardRotation => "downward rotation",
+            JointAction::Pronation => "pronation",
+            JointAction::Supination => "supination",
+            JointAction::RadialDeviation => "radial deviation",
+            JointAction::UlnarDeviation => "ulnar deviation",
+            JointAction::LateralFlexion => "lateral flexion",
+            JointAction::Rotation => "rotation",
+            JointAction::PlantarFlexion => "plantar flexion",
+            JointAction::Dorsiflexion => "dorsiflexion",
+            JointAction::Inversion => "inversion",
+            JointAction::Eversion => "eversion",
+        }
+    }
+}
+
+impl fmt::Display for JointAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl FromStr for JointAction {
+    type Err = String;
+
+    /// Accepts the label or the serialized name in any case, with spaces,
+    /// underscores or hyphens between words.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let wanted: String = s
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        JointAction::ALL
+            .into_iter()
+            .find(|action| action.label().replace(' ', "") == wanted)
+            .ok_or_else(|| format!("unknown joint action '{}'", s))
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema, PartialEq, Clone)]
 #[serde(rename_all = "lowercase")]
 pub enum ResistanceProfile {
     Ascending,
     Descending,
     Bell,
+    #[default]
     Constant,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema, PartialEq, Clone)]
 #[serde(rename_all = "lowercase")]
 pub enum PlaneOfMotion {
     Sagittal,
     Frontal,
+    #[default]
     Transverse,
-} 
\ No newline at end of file
+}
+
+impl ResistanceProfile {
+    pub const ALL: [ResistanceProfile; 4] = [
+        ResistanceProfile::Ascending,
+        ResistanceProfile::Descending,
+        ResistanceProfile::Bell,
+        ResistanceProfile::Constant,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ResistanceProfile::Ascending => "ascending",
+            ResistanceProfile::Descending => "descending",
+            ResistanceProfile::Bell => "bell",
+            ResistanceProfile::Constant => "constant",
+        }
+    }
+}
+
+impl PlaneOfMotion {
+    pub const ALL: [PlaneOfMotion; 3] = [PlaneOfMotion::Sagittal, PlaneOfMotion::Frontal, PlaneOfMotion::Transverse];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PlaneOfMotion::Sagittal => "sagittal",
+            PlaneOfMotion::Frontal => "frontal",
+            PlaneOfMotion::Transverse => "transverse",
+        }
+    }
+}
+
+/// Version of the single-file collection format written by `pack`.
+pub const COLLECTION_SCHEMA_VERSION: u32 = 1;
+
+/// Every exercise in the archive bundled into one versioned document.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExerciseCollection {
+    pub schema_version: u32,
+    pub generated_at: DateTime<Utc>,
+    pub counts: CollectionCounts,
+    pub exercises: Vec<Exercise>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct CollectionCounts {
+    pub exercises: usize,
+    pub compound: usize,
+    pub isolation: usize,
+}
+
+impl ExerciseCollection {
+    pub fn new(exercises: Vec<Exercise>) -> Self {
+        let counts = CollectionCounts {
+            exercises: exercises.len(),
+            compound: exercises.iter().filter(|e| e.r#type == ExerciseType::Compound).count(),
+            isolation: exercises.iter().filter(|e| e.r#type == ExerciseType::Isolation).count(),
+        };
+        Self {
+            schema_version: COLLECTION_SCHEMA_VERSION,
+            generated_at: Utc::now(),
+            counts,
+            exercises,
+        }
+    }
+}
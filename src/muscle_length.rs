@@ -0,0 +1,236 @@
+//! Estimated muscle length through an exercise's range of motion, and how
+//! much of the resistance arrives while each targeted division is long.
+//!
+//! Lengths are relative: 0 is the shortest the taxonomy's length model allows
+//! and 1 the longest. Dynamic joints are assumed to move through their ranges
+//! together; static joints stay at their angle.
+
+use crate::models::*;
+use crate::taxonomy::{JointLength, LengthModel, Taxonomy};
+
+/// Positions through the movement at which length and resistance are sampled.
+const STEPS: usize = 36;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StretchScore {
+    pub muscle: String,
+    pub division: String,
+    /// Average length of the division weighted by the resistance at each
+    /// point, from 0 (all load at the shortest length) to 1 (all at the longest).
+    pub score: f64,
+    /// Longest length the division reaches in the movement.
+    pub longest: f64,
+}
+
+impl JointLength {
+    /// Relative length contributed by this joint at `angle`.
+    pub fn length_at(&self, angle: f64) -> f64 {
+        let (longest, shortest) = (self.longest_at as f64, self.shortest_at as f64);
+        if longest == shortest {
+            return 1.0;
+        }
+        ((angle - shortest) / (longest - shortest)).clamp(0.0, 1.0)
+    }
+}
+
+impl LengthModel {
+    /// Relative length given the angle of each joint, averaged over the
+    /// crossed joints by weight. Joints without an angle are left out;
+    /// `None` if none of them has one.
+    pub fn length(&self, angle_of: impl Fn(&str) -> Option<f64>) -> Option<f64> {
+        let (sum, weights) = self
+            .joints
+            .iter()
+            .filter_map(|j| Some((j.length_at(angle_of(&j.joint)?), j.weight)))
+            .fold((0.0, 0.0), |(sum, weights), (length, weight)| {
+                (sum + length * weight, weights + weight)
+            });
+        (weights > 0.0).then(|| sum / weights)
+    }
+}
+
+impl Exercise {
+    /// Angle of the named joint `progress` (0 to 1) of the way through the movement.
+    pub fn joint_angle_at(&self, name: &str, progress: f64) -> Option<f64> {
+        let joint = self.joints_involved.joints.iter().find(|j| j.name == name)?;
+        if joint.dynamic {
+            let from = joint.angle_initial? as f64;
+            let to = joint.angle_final? as f64;
+            Some(from + (to - from) * progress)
+        } else {
+            joint.angle.map(|a| a as f64)
+        }
+    }
+
+    /// Relative resistance `progress` of the way through the movement, from
+    /// the resistance curve when there is one and otherwise from the shape
+    /// of `resistance_profile`.
+    pub fn resistance_at(&self, progress: f64) -> f64 {
+        if let Some(joint) = self.curve_joint()
+            && let (Some(from), Some(to)) = (joint.angle_initial, joint.angle_final)
+            && let Some(curve) = &joint.resistance_curve
+        {
+            let angle = from as f64 + (to as f64 - from as f64) * progress;
+            return curve.normalized().torque_at(angle).unwrap_or(0.0);
+        }
+        match self.resistance_profile {
+            ResistanceProfile::Ascending => progress,
+            ResistanceProfile::Descending => 1.0 - progress,
+            ResistanceProfile::Bell => (progress * std::f64::consts::PI).sin(),
+            ResistanceProfile::Constant => 1.0,
+        }
+    }
+
+    /// Stretch score of one division, if the taxonomy has a length model for
+    /// it that involves at least one of the exercise's joints.
+    pub fn stretch_score(&self, taxonomy: &Taxonomy, muscle: &str, division: &str) -> Option<StretchScore> {
+        let model = taxonomy.length_model(muscle, division)?;
+        let mut weighted = 0.0;
+        let mut total = 0.0;
+        let mut longest: f64 = 0.0;
+        for step in 0..=STEPS {
+            let progress = step as f64 / STEPS as f64;
+            let length = model.length(|joint| self.joint_angle_at(joint, progress))?;
+            let resistance = self.resistance_at(progress);
+            weighted += length * resistance;
+            total += resistance;
+            longest = longest.max(length);
+        }
+        Some(StretchScore {
+            muscle: muscle.to_string(),
+            division: division.to_string(),
+            score: if total > 0.0 { weighted / total } else { 0.0 },
+            longest,
+        })
+    }
+
+    /// Stretch scores of every division the exercise puts some emphasis on.
+    pub fn stretch_scores(&self, taxonomy: &Taxonomy) -> Vec<StretchScore> {
+        self.target_muscles
+            .muscles
+            .iter()
+            .flat_map(|muscle| {
+                muscle
+                    .active_divisions()
+                    .filter_map(|d| self.stretch_score(taxonomy, &muscle.muscle_name, &d.name))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn joint(name: &str, from: u32, to: u32) -> Joint {
+        Joint {
+            name: name.to_string(),
+            dynamic: from != to,
+            angle: (from == to).then_some(from),
+            direction: (from != to).then_some(JointAction::Flexion),
+            unmapped_direction: None,
+            angle_initial: (from != to).then_some(from),
+            angle_final: (from != to).then_some(to),
+            resistance_curve: None,
+        }
+    }
+
+    /// A curl from straight arm to 30° with the shoulder held at `shoulder`.
+    fn curl(profile: ResistanceProfile, shoulder: u32) -> Exercise {
+        Exercise {
+            target_muscles: TargetMuscles {
+                muscles: vec![TargetMuscle {
+                    muscle_divisions: vec![
+                        MuscleDivision { name: "long head".to_string(), emphasis: Emphasis::High },
+                        MuscleDivision { name: "short head".to_string(), emphasis: Emphasis::None },
+                    ],
+                    ..TargetMuscle::new("biceps".to_string(), MuscleRole::Primary)
+                }],
+            },
+            joints_involved: JointsInvolved {
+                joints: vec![joint("elbow", 180, 30), joint("shoulder", shoulder, shoulder)],
+            },
+            resistance_profile: profile,
+            ..Exercise::default()
+        }
+    }
+
+    fn score(exercise: &Exercise, muscle: &str, division: &str) -> f64 {
+        exercise.stretch_score(&Taxonomy::builtin(), muscle, division).unwrap().score
+    }
+
+    #[test]
+    fn joint_length_is_clamped_between_its_extremes() {
+        let elbow = JointLength { joint: "elbow".to_string(), longest_at: 180, shortest_at: 30, weight: 1.0 };
+        assert_eq!(elbow.length_at(105.0), 0.5);
+        assert_eq!(elbow.length_at(0.0), 0.0);
+        assert_eq!(elbow.length_at(180.0), 1.0);
+        // A joint that does not change the length counts as long throughout
+        let fixed = JointLength { longest_at: 90, shortest_at: 90, ..elbow };
+        assert_eq!(fixed.length_at(10.0), 1.0);
+    }
+
+    #[test]
+    fn length_is_averaged_by_joint_weight() {
+        let model = Taxonomy::builtin().length_model("biceps", "long head").unwrap().clone();
+        // Elbow half way (weight 1), shoulder fully lengthened (weight 0.5)
+        let length = model.length(|j| Some(if j == "elbow" { 105.0 } else { 0.0 })).unwrap();
+        assert!((length - (0.5 + 0.5) / 1.5).abs() < 1e-9);
+        // Joints without an angle are left out
+        assert_eq!(model.length(|j| (j == "elbow").then_some(30.0)), Some(0.0));
+        assert_eq!(model.length(|_| None), None);
+    }
+
+    #[test]
+    fn loading_the_start_of_a_curl_scores_the_stretch() {
+        let descending = score(&curl(ResistanceProfile::Descending, 60), "brachialis", "brachialis");
+        let constant = score(&curl(ResistanceProfile::Constant, 60), "brachialis", "brachialis");
+        let ascending = score(&curl(ResistanceProfile::Ascending, 60), "brachialis", "brachialis");
+        assert!((constant - 0.5).abs() < 1e-9);
+        assert!(descending > constant && constant > ascending);
+        // Length falls linearly with progress, so the two ramps mirror each other
+        assert!((descending + ascending - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn shoulder_position_changes_the_long_head_only() {
+        let incline = curl(ResistanceProfile::Constant, 0);
+        let preacher = curl(ResistanceProfile::Constant, 90);
+        assert!(score(&incline, "biceps", "long head") > score(&preacher, "biceps", "long head"));
+        assert_eq!(score(&incline, "brachialis", "brachialis"), score(&preacher, "brachialis", "brachialis"));
+
+        let longest = incline.stretch_score(&Taxonomy::builtin(), "biceps", "long head").unwrap().longest;
+        assert!((longest - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn resistance_curve_takes_precedence_over_the_profile() {
+        let mut exercise = curl(ResistanceProfile::Ascending, 60);
+        // All of the load near the straight arm, none once the elbow is bent
+        exercise.joints_involved.joints[0].resistance_curve = Some(ResistanceCurve {
+            samples: vec![
+                TorqueSample { angle: 180, torque: 4.0 },
+                TorqueSample { angle: 150, torque: 0.0 },
+                TorqueSample { angle: 30, torque: 0.0 },
+            ],
+        });
+        assert_eq!(exercise.resistance_at(0.0), 1.0);
+        assert_eq!(exercise.resistance_at(0.5), 0.0);
+        assert!(score(&exercise, "brachialis", "brachialis") > 0.9);
+    }
+
+    #[test]
+    fn only_modelled_active_divisions_are_scored() {
+        let exercise = curl(ResistanceProfile::Constant, 0);
+        let scored: Vec<(String, String)> = exercise
+            .stretch_scores(&Taxonomy::builtin())
+            .into_iter()
+            .map(|s| (s.muscle, s.division))
+            .collect();
+        assert_eq!(scored, [("biceps".to_string(), "long head".to_string())]);
+
+        assert_eq!(exercise.stretch_score(&Taxonomy::builtin(), "biceps", "no such head"), None);
+        // The model needs a joint the exercise uses
+        assert_eq!(exercise.stretch_score(&Taxonomy::builtin(), "hamstrings", "biceps femoris"), None);
+    }
+}
@@ -0,0 +1,132 @@
+//! Training programs assembled from archive exercises.
+//!
+//! A program is one mesocycle: a run of weeks, each made of sessions, each a
+//! list of slots that point at an exercise by its `Exercise.id` and give the
+//! sets, rep range, reps in reserve and rest for it. Programs are stored as
+//! one JSON file each in a `programs` folder next to the data directory.
+
+use crate::library::{json_files, sanitize_filename, write_atomic, Library, StorageError};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Program {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+    #[serde(default)]
+    pub weeks: Vec<Week>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Week {
+    /// A lighter week to recover before the next block.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deload: bool,
+    #[serde(default)]
+    pub sessions: Vec<Session>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Session {
+    pub name: String,
+    #[serde(default)]
+    pub slots: Vec<Slot>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Slot {
+    pub exercise_id: u32,
+    pub sets: u32,
+    pub reps: RepRange,
+    /// Reps in reserve to stop each set at.
+    pub rir: u32,
+    /// Rest between sets, in seconds.
+    pub rest_seconds: u32,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+}
+
+impl Slot {
+    pub fn new(exercise_id: u32) -> Self {
+        Self {
+            exercise_id,
+            sets: 3,
+            reps: RepRange { min: 8, max: 12 },
+            rir: 2,
+            rest_seconds: 120,
+            notes: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RepRange {
+    pub min: u32,
+    pub max: u32,
+}
+
+impl std::fmt::Display for RepRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.min == self.max {
+            write!(f, "{}", self.min)
+        } else {
+            write!(f, "{}-{}", self.min, self.max)
+        }
+    }
+}
+
+impl Program {
+    pub fn load(path: &Path) -> Result<Program, StorageError> {
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), StorageError> {
+        let json = serde_json::to_string_pretty(self)?;
+        write_atomic(path, json.as_bytes())?;
+        Ok(())
+    }
+
+    /// Where programs for a data directory live: a `programs` folder beside it.
+    pub fn dir_for_data_dir(data_dir: &Path) -> PathBuf {
+        data_dir.parent().unwrap_or(Path::new("")).join("programs")
+    }
+
+    /// The file this program is saved to by default inside `dir`.
+    pub fn default_path(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("{}.json", sanitize_filename(&self.name)))
+    }
+
+    /// Every slot in the program, in order.
+    pub fn slots(&self) -> impl Iterator<Item = &Slot> {
+        self.weeks.iter().flat_map(|w| &w.sessions).flat_map(|s| &s.slots)
+    }
+
+    /// Exercise ids used by the program that the library does not contain.
+    pub fn unknown_exercise_ids(&self, library: &Library) -> Vec<u32> {
+        let mut ids: Vec<u32> = self
+            .slots()
+            .map(|slot| slot.exercise_id)
+            .filter(|&id| library.find_by_id(id).is_none())
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+}
+
+/// Reads every `*.json` program in `dir`, sorted by path. Files that fail to
+/// parse are returned with their error rather than ending the scan, and a
+/// missing directory has no programs.
+pub fn load_programs(dir: &Path) -> io::Result<Vec<(PathBuf, Result<Program, StorageError>)>> {
+    Ok(json_files(dir)?
+        .into_iter()
+        .map(|path| {
+            let program = Program::load(&path);
+            (path, program)
+        })
+        .collect())
+}
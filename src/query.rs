@@ -0,0 +1,290 @@
+//! Filtering the library by what an exercise trains and how it loads it.
+
+use crate::library::{Library, LibraryEntry};
+use crate::models::*;
+use crate::taxonomy::Taxonomy;
+
+/// A set of filters that must all match. Unset filters match everything.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExerciseQuery {
+    pub muscle_name: Option<String>,
+    /// Restricts `muscle_name` (or any target muscle) to one role.
+    pub muscle_role: Option<MuscleRole>,
+    /// Name of a division with some emphasis, on a muscle matching the filters above.
+    pub division: Option<String>,
+    /// Lowest stretch score (0 to 1) an active division matching the filters
+    /// above must reach; see `muscle_length`.
+    pub min_stretch: Option<f64>,
+    pub joint: Option<String>,
+    /// Restricts `joint` to dynamic (`Some(true)`) or static (`Some(false)`) use.
+    pub joint_dynamic: Option<bool>,
+    /// Action performed by a dynamic joint matching the filters above.
+    pub joint_action: Option<JointAction>,
+    pub plane_of_motion: Option<PlaneOfMotion>,
+    /// Matches the exercise's own profile or the profile of any of its
+    /// variants (only variants with `equipment`, if that is set).
+    pub resistance_profile: Option<ResistanceProfile>,
+    /// Equipment one of the exercise's variants uses.
+    pub equipment: Option<Equipment>,
+    pub exercise_type: Option<ExerciseType>,
+    /// Case-insensitive text searched for in the name, description and tips.
+    pub text: Option<String>,
+}
+
+impl ExerciseQuery {
+    pub fn is_empty(&self) -> bool {
+        *self == ExerciseQuery::default()
+    }
+
+    /// Whether `exercise` passes every filter. The taxonomy supplies the
+    /// muscle length models used by `min_stretch`.
+    pub fn matches(&self, exercise: &Exercise, taxonomy: &Taxonomy) -> bool {
+        if self.muscle_name.is_some()
+            || self.muscle_role.is_some()
+            || self.division.is_some()
+            || self.min_stretch.is_some()
+        {
+            let targeted = exercise.target_muscles.muscles.iter().any(|muscle| {
+                let mut divisions = muscle.active_divisions().filter(|d| {
+                    self.division.as_ref().is_none_or(|division| d.name.eq_ignore_ascii_case(division))
+                });
+                self.muscle_name.as_ref().is_none_or(|name| muscle.muscle_name.eq_ignore_ascii_case(name))
+                    && self.muscle_role.is_none_or(|role| muscle.role == role)
+                    && match self.min_stretch {
+                        Some(min) => divisions.any(|d| {
+                            exercise
+                                .stretch_score(taxonomy, &muscle.muscle_name, &d.name)
+                                .is_some_and(|s| s.score >= min)
+                        }),
+                        None => self.division.is_none() || divisions.next().is_some(),
+                    }
+            });
+            if !targeted {
+                return false;
+            }
+        }
+
+        if self.joint.is_some() || self.joint_dynamic.is_some() || self.joint_action.is_some() {
+            let used = exercise.joints_involved.joints.iter().any(|joint| {
+                self.joint.as_ref().is_none_or(|name| joint.name.eq_ignore_ascii_case(name))
+                    && self.joint_dynamic.is_none_or(|dynamic| joint.dynamic == dynamic)
+                    && self.joint_action.is_none_or(|action| joint.dynamic && joint.direction == Some(action))
+            });
+            if !used {
+                return false;
+            }
+        }
+
+        if self.plane_of_motion.as_ref().is_some_and(|p| *p != exercise.plane_of_motion) {
+            return false;
+        }
+        if let Some(equipment) = self.equipment {
+            let available = exercise.variants.iter().any(|v| {
+                v.equipment == equipment
+                    && self.resistance_profile.as_ref().is_none_or(|p| *p == v.resistance_profile)
+            });
+            if !available {
+                return false;
+            }
+        } else if let Some(profile) = &self.resistance_profile {
+            let produced = *profile == exercise.resistance_profile
+                || exercise.variants.iter().any(|v| v.resistance_profile == *profile);
+            if !produced {
+                return false;
+            }
+        }
+        if self.exercise_type.as_ref().is_some_and(|t| *t != exercise.r#type) {
+            return false;
+        }
+
+        if let Some(text) = &self.text {
+            let needle = text.to_lowercase();
+            let found = [&exercise.name, &exercise.description, &exercise.tips]
+                .iter()
+                .any(|field| field.to_lowercase().contains(&needle));
+            if !found {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+impl Library {
+    /// Entries whose exercise matches every filter in `query`, in library order.
+    pub fn search(&self, query: &ExerciseQuery, taxonomy: &Taxonomy) -> Vec<&LibraryEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| query.matches(&entry.exercise, taxonomy))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn division(name: &str, emphasis: Emphasis) -> MuscleDivision {
+        MuscleDivision { name: name.to_string(), emphasis }
+    }
+
+    fn variant(equipment: Equipment, resistance_profile: ResistanceProfile) -> EquipmentVariant {
+        EquipmentVariant { equipment, resistance_profile, ..EquipmentVariant::default() }
+    }
+
+    /// An incline dumbbell curl: the shoulder is held extended so the long head starts stretched.
+    fn incline_curl() -> Exercise {
+        Exercise {
+            name: "Incline curl".to_string(),
+            r#type: ExerciseType::Isolation,
+            tips: "Let the arms hang BEHIND the torso".to_string(),
+            target_muscles: TargetMuscles {
+                muscles: vec![
+                    TargetMuscle {
+                        muscle_divisions: vec![
+                            division("long head", Emphasis::High),
+                            division("short head", Emphasis::None),
+                        ],
+                        ..TargetMuscle::new("biceps".to_string(), MuscleRole::Primary)
+                    },
+                    TargetMuscle {
+                        muscle_divisions: vec![division("brachialis", Emphasis::Moderate)],
+                        ..TargetMuscle::new("brachialis".to_string(), MuscleRole::Secondary)
+                    },
+                ],
+            },
+            joints_involved: JointsInvolved {
+                joints: vec![
+                    Joint {
+                        name: "elbow".to_string(),
+                        dynamic: true,
+                        angle: None,
+                        direction: Some(JointAction::Flexion),
+                        unmapped_direction: None,
+                        angle_initial: Some(180),
+                        angle_final: Some(30),
+                        resistance_curve: None,
+                    },
+                    Joint {
+                        name: "shoulder".to_string(),
+                        dynamic: false,
+                        angle: Some(0),
+                        direction: None,
+                        unmapped_direction: None,
+                        angle_initial: None,
+                        angle_final: None,
+                        resistance_curve: None,
+                    },
+                ],
+            },
+            resistance_profile: ResistanceProfile::Descending,
+            plane_of_motion: PlaneOfMotion::Sagittal,
+            variants: vec![
+                variant(Equipment::Dumbbell, ResistanceProfile::Descending),
+                variant(Equipment::Cable, ResistanceProfile::Constant),
+            ],
+            ..Exercise::default()
+        }
+    }
+
+    fn matches(query: ExerciseQuery) -> bool {
+        query.matches(&incline_curl(), &Taxonomy::builtin())
+    }
+
+    #[test]
+    fn empty_query_matches_everything() {
+        assert!(ExerciseQuery::default().is_empty());
+        assert!(matches(ExerciseQuery::default()));
+    }
+
+    #[test]
+    fn muscle_role_and_division_must_hold_for_the_same_muscle() {
+        let muscle = |name: &str, role| ExerciseQuery {
+            muscle_name: Some(name.to_string()),
+            muscle_role: Some(role),
+            ..ExerciseQuery::default()
+        };
+        assert!(matches(muscle("Biceps", MuscleRole::Primary)));
+        assert!(!matches(muscle("biceps", MuscleRole::Secondary)));
+        assert!(matches(muscle("brachialis", MuscleRole::Secondary)));
+
+        let division = |muscle: Option<&str>, division: &str| ExerciseQuery {
+            muscle_name: muscle.map(str::to_string),
+            division: Some(division.to_string()),
+            ..ExerciseQuery::default()
+        };
+        assert!(matches(division(None, "Long Head")));
+        assert!(matches(division(Some("biceps"), "long head")));
+        // Listed without emphasis
+        assert!(!matches(division(None, "short head")));
+        assert!(!matches(division(Some("brachialis"), "long head")));
+    }
+
+    #[test]
+    fn min_stretch_needs_an_active_division_that_long() {
+        let stretch = |division: Option<&str>, min| ExerciseQuery {
+            division: division.map(str::to_string),
+            min_stretch: Some(min),
+            ..ExerciseQuery::default()
+        };
+        // Descending load with the shoulder extended keeps the long head long under load
+        let long_head = incline_curl().stretch_score(&Taxonomy::builtin(), "biceps", "long head").unwrap().score;
+        assert!(long_head > 0.7);
+        assert!(matches(stretch(None, 0.7)));
+        assert!(matches(stretch(Some("long head"), long_head)));
+        assert!(!matches(stretch(Some("long head"), long_head + 0.01)));
+        // The short head is stretched too, but has no emphasis
+        assert!(!matches(stretch(Some("short head"), 0.0)));
+    }
+
+    #[test]
+    fn joint_filters_apply_to_one_joint() {
+        let joint = |name: &str, dynamic: Option<bool>, action: Option<JointAction>| ExerciseQuery {
+            joint: Some(name.to_string()),
+            joint_dynamic: dynamic,
+            joint_action: action,
+            ..ExerciseQuery::default()
+        };
+        assert!(matches(joint("elbow", Some(true), Some(JointAction::Flexion))));
+        assert!(!matches(joint("elbow", None, Some(JointAction::Extension))));
+        assert!(matches(joint("shoulder", Some(false), None)));
+        assert!(!matches(joint("shoulder", Some(true), None)));
+        // Static joints perform no action
+        assert!(!matches(joint("shoulder", None, Some(JointAction::Flexion))));
+    }
+
+    #[test]
+    fn plane_type_and_text_filters() {
+        let plane = |plane| ExerciseQuery { plane_of_motion: Some(plane), ..ExerciseQuery::default() };
+        assert!(matches(plane(PlaneOfMotion::Sagittal)));
+        assert!(!matches(plane(PlaneOfMotion::Frontal)));
+
+        let kind = |t| ExerciseQuery { exercise_type: Some(t), ..ExerciseQuery::default() };
+        assert!(matches(kind(ExerciseType::Isolation)));
+        assert!(!matches(kind(ExerciseType::Compound)));
+
+        let text = |t: &str| ExerciseQuery { text: Some(t.to_string()), ..ExerciseQuery::default() };
+        assert!(matches(text("behind the torso")));
+        assert!(matches(text("INCLINE")));
+        assert!(!matches(text("preacher")));
+    }
+
+    #[test]
+    fn profile_matches_the_exercise_or_a_variant_with_the_equipment() {
+        let profile = |profile, equipment| ExerciseQuery {
+            resistance_profile: Some(profile),
+            equipment,
+            ..ExerciseQuery::default()
+        };
+        assert!(matches(profile(ResistanceProfile::Descending, None)));
+        assert!(matches(profile(ResistanceProfile::Constant, None)));
+        assert!(!matches(profile(ResistanceProfile::Ascending, None)));
+        assert!(matches(profile(ResistanceProfile::Constant, Some(Equipment::Cable))));
+        assert!(!matches(profile(ResistanceProfile::Constant, Some(Equipment::Dumbbell))));
+
+        let equipment = |e| ExerciseQuery { equipment: Some(e), ..ExerciseQuery::default() };
+        assert!(matches(equipment(Equipment::Dumbbell)));
+        assert!(!matches(equipment(Equipment::Barbell)));
+    }
+}
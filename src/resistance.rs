@@ -0,0 +1,215 @@
+//! Resistance curves: how the torque an exercise places on its moving joint
+//! changes across the range of motion, and the coarse `ResistanceProfile`
+//! label derived from that shape.
+
+use crate::models::*;
+
+/// Relative change in torque below which a curve counts as constant, and by
+/// which an interior peak must exceed both ends to count as a bell.
+const FLAT_TOLERANCE: f64 = 0.15;
+
+impl ResistanceCurve {
+    /// `count` evenly spaced samples from `from` to `to`, with torque given by `torque`.
+    pub fn sampled(from: u32, to: u32, count: usize, torque: impl Fn(u32) -> f64) -> Self {
+        let count = count.max(2);
+        let samples = (0..count)
+            .map(|i| {
+                let t = i as f64 / (count - 1) as f64;
+                let angle = (from as f64 + (to as f64 - from as f64) * t).round() as u32;
+                TorqueSample { angle, torque: torque(angle) }
+            })
+            .collect();
+        Self { samples }
+    }
+
+    /// Samples ordered by angle.
+    pub fn sorted(&self) -> Vec<TorqueSample> {
+        let mut samples = self.samples.clone();
+        samples.sort_by_key(|s| s.angle);
+        samples
+    }
+
+    /// Torque at `angle`, interpolated linearly between the nearest samples.
+    /// `None` outside the sampled range.
+    pub fn torque_at(&self, angle: f64) -> Option<f64> {
+        let points: Vec<(f64, f64)> = self.sorted().iter().map(|s| (s.angle as f64, s.torque)).collect();
+        interpolate(&points, angle)
+    }
+
+    /// The sample with the highest torque.
+    pub fn peak(&self) -> Option<TorqueSample> {
+        self.samples.iter().copied().max_by(|a, b| a.torque.total_cmp(&b.torque))
+    }
+
+    /// The same curve scaled so its peak torque is 1, for comparing curves
+    /// recorded in different units or with different loads.
+    pub fn normalized(&self) -> Self {
+        let peak = self.peak().map_or(0.0, |p| p.torque);
+        let scale = if peak > 0.0 { 1.0 / peak } else { 1.0 };
+        Self {
+            samples: self
+                .samples
+                .iter()
+                .map(|s| TorqueSample { angle: s.angle, torque: s.torque * scale })
+                .collect(),
+        }
+    }
+
+    /// The coarse profile of the curve when the joint moves from `from` to
+    /// `to`: ascending if it gets harder towards `to`, descending if it gets
+    /// easier, bell if it peaks in between. `None` without at least two
+    /// samples or any positive torque.
+    pub fn classify(&self, from: u32, to: u32) -> Option<ResistanceProfile> {
+        let mut samples = self.sorted();
+        if from > to {
+            samples.reverse();
+        }
+        if samples.len() < 2 {
+            return None;
+        }
+
+        let torques: Vec<f64> = samples.iter().map(|s| s.torque).collect();
+        let max = torques.iter().copied().fold(f64::MIN, f64::max);
+        let min = torques.iter().copied().fold(f64::MAX, f64::min);
+        if max <= 0.0 {
+            return None;
+        }
+        if (max - min) / max < FLAT_TOLERANCE {
+            return Some(ResistanceProfile::Constant);
+        }
+
+        let first = torques[0];
+        let last = torques[torques.len() - 1];
+        let peak_index = torques.iter().position(|&t| t == max).unwrap_or(0);
+        let interior_peak = peak_index != 0 && peak_index != torques.len() - 1;
+        if interior_peak && (max - first.max(last)) / max >= FLAT_TOLERANCE {
+            Some(ResistanceProfile::Bell)
+        } else if last > first {
+            Some(ResistanceProfile::Ascending)
+        } else {
+            Some(ResistanceProfile::Descending)
+        }
+    }
+}
+
+/// Linear interpolation over `points` sorted by x. `None` outside their range.
+pub(crate) fn interpolate(points: &[(f64, f64)], x: f64) -> Option<f64> {
+    let first = points.first()?;
+    let last = points.last()?;
+    if x < first.0 || x > last.0 {
+        return None;
+    }
+    if points.len() == 1 {
+        return Some(first.1);
+    }
+    points.windows(2).find_map(|pair| {
+        let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+        if x < x0 || x > x1 {
+            return None;
+        }
+        if x1 == x0 {
+            return Some(y0.max(y1));
+        }
+        Some(y0 + (y1 - y0) * (x - x0) / (x1 - x0))
+    })
+}
+
+impl Joint {
+    /// Profile of this joint's resistance curve over its range of motion.
+    pub fn derived_resistance_profile(&self) -> Option<ResistanceProfile> {
+        let curve = self.resistance_curve.as_ref().filter(|_| self.dynamic)?;
+        let from = self.angle_initial?;
+        let to = self.angle_final?;
+        curve.classify(from, to)
+    }
+}
+
+impl Exercise {
+    /// The first dynamic joint that has a resistance curve.
+    pub fn curve_joint(&self) -> Option<&Joint> {
+        self.joints_involved
+            .joints
+            .iter()
+            .find(|j| j.dynamic && j.resistance_curve.is_some())
+    }
+
+    /// Profile derived from the resistance curve, if the exercise has one.
+    pub fn derived_resistance_profile(&self) -> Option<ResistanceProfile> {
+        self.curve_joint()?.derived_resistance_profile()
+    }
+
+    /// Sets `resistance_profile` from the resistance curve. Exercises without
+    /// a usable curve keep the label they have.
+    pub fn sync_resistance_profile(&mut self) {
+        if let Some(profile) = self.derived_resistance_profile() {
+            self.resistance_profile = profile;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curve(samples: &[(u32, f64)]) -> ResistanceCurve {
+        ResistanceCurve {
+            samples: samples.iter().map(|&(angle, torque)| TorqueSample { angle, torque }).collect(),
+        }
+    }
+
+    #[test]
+    fn classify_follows_the_direction_of_movement() {
+        let rising = curve(&[(40, 0.5), (110, 0.75), (180, 1.0)]);
+        assert_eq!(rising.classify(40, 180), Some(ResistanceProfile::Ascending));
+        assert_eq!(rising.classify(180, 40), Some(ResistanceProfile::Descending));
+    }
+
+    #[test]
+    fn classify_flat_boundary() {
+        // Just under the 15% tolerance is constant, just over is not
+        assert_eq!(curve(&[(0, 0.86), (90, 1.0)]).classify(0, 90), Some(ResistanceProfile::Constant));
+        assert_eq!(curve(&[(0, 0.84), (90, 1.0)]).classify(0, 90), Some(ResistanceProfile::Ascending));
+        assert_eq!(curve(&[(0, 1.0), (90, 1.0)]).classify(0, 90), Some(ResistanceProfile::Constant));
+    }
+
+    #[test]
+    fn classify_bell_boundary() {
+        // The interior peak has to clear the higher end by the tolerance
+        let bell = curve(&[(0, 0.8), (45, 1.0), (90, 0.5)]);
+        assert_eq!(bell.classify(0, 90), Some(ResistanceProfile::Bell));
+        let shallow = curve(&[(0, 0.9), (45, 1.0), (90, 0.5)]);
+        assert_eq!(shallow.classify(0, 90), Some(ResistanceProfile::Descending));
+        let end_peak = curve(&[(0, 0.5), (45, 0.9), (90, 1.0)]);
+        assert_eq!(end_peak.classify(0, 90), Some(ResistanceProfile::Ascending));
+    }
+
+    #[test]
+    fn classify_needs_two_samples_and_positive_torque() {
+        assert_eq!(curve(&[(0, 1.0)]).classify(0, 90), None);
+        assert_eq!(curve(&[(0, 0.0), (90, 0.0)]).classify(0, 90), None);
+        assert_eq!(curve(&[]).classify(0, 90), None);
+    }
+
+    #[test]
+    fn interpolate_inside_and_outside_the_range() {
+        let points = [(0.0, 0.0), (90.0, 1.0), (180.0, 0.0)];
+        assert_eq!(interpolate(&points, 45.0), Some(0.5));
+        assert_eq!(interpolate(&points, 90.0), Some(1.0));
+        assert_eq!(interpolate(&points, 0.0), Some(0.0));
+        assert_eq!(interpolate(&points, 180.0), Some(0.0));
+        assert_eq!(interpolate(&points, -0.1), None);
+        assert_eq!(interpolate(&points, 180.1), None);
+        assert_eq!(interpolate(&[], 0.0), None);
+        assert_eq!(interpolate(&[(30.0, 2.0)], 30.0), Some(2.0));
+        assert_eq!(interpolate(&[(30.0, 2.0)], 31.0), None);
+        // Two samples at the same angle give the larger torque
+        assert_eq!(interpolate(&[(30.0, 2.0), (30.0, 3.0)], 30.0), Some(3.0));
+    }
+
+    #[test]
+    fn torque_at_sorts_unordered_samples() {
+        let unordered = curve(&[(180, 0.0), (0, 0.0), (90, 1.0)]);
+        assert_eq!(unordered.torque_at(135.0), Some(0.5));
+        assert_eq!(unordered.torque_at(200.0), None);
+    }
+}
@@ -0,0 +1,117 @@
+//! JSON Schema for exercise files, so they can be validated without Rust.
+
+use crate::models::{Exercise, CURRENT_SCHEMA_VERSION};
+use crate::taxonomy::Taxonomy;
+use serde_json::{json, Value};
+
+/// Builds the JSON Schema for a current-version exercise document.
+///
+/// The structural part is derived from the `models` types; the rules that
+/// depend on other fields (which angles a joint needs, which actions
+/// `taxonomy` allows for each joint) are added on top.
+pub fn exercise_schema(taxonomy: &Taxonomy) -> Value {
+    let mut schema = schemars::schema_for!(Exercise).to_value();
+
+    schema["properties"]["schema_version"] = json!({
+        "const": CURRENT_SCHEMA_VERSION,
+        "description": "Version of the exercise file format"
+    });
+
+    if let Some(joint) = schema.pointer_mut("/$defs/Joint") {
+        joint["allOf"] = json!([{
+            "if": {
+                "properties": { "dynamic": { "const": true } }
+            },
+            "then": {
+                "description": "Dynamic joints move through a range and need a direction",
+                "required": ["direction", "angle_initial", "angle_final"],
+                "properties": {
+                    "direction": { "$ref": "#/$defs/JointAction" },
+                    "angle_initial": { "type": "integer" },
+                    "angle_final": { "type": "integer" }
+                },
+                "not": { "required": ["angle"] }
+            },
+            "else": {
+                "description": "Static joints are held at a single angle",
+                "required": ["angle"],
+                "properties": {
+                    "angle": { "type": "integer" }
+                },
+                "not": {
+                    "anyOf": [
+                        { "required": ["direction"] },
+                        { "required": ["unmapped_direction"] },
+                        { "required": ["angle_initial"] },
+                        { "required": ["angle_final"] },
+                        { "required": ["resistance_curve"] }
+                    ]
+                }
+            }
+        }]);
+
+        let all_of = joint["allOf"].as_array_mut().expect("allOf was just set");
+        for spec in taxonomy.joints.iter().filter(|j| !j.actions.is_empty()) {
+            all_of.push(json!({
+                "if": {
+                    "properties": { "name": { "const": spec.name } }
+                },
+                "then": {
+                    "properties": { "direction": { "enum": spec.actions } }
+                }
+            }));
+        }
+    }
+
+    schema
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::JointAction;
+    use crate::taxonomy::JointSpec;
+
+    fn joint_rules(schema: &Value) -> &Vec<Value> {
+        schema.pointer("/$defs/Joint/allOf").and_then(Value::as_array).expect("Joint has allOf rules")
+    }
+
+    #[test]
+    fn dynamic_and_static_joints_need_different_fields() {
+        let schema = exercise_schema(&Taxonomy::builtin());
+        let rule = &joint_rules(&schema)[0];
+        assert_eq!(rule["if"]["properties"]["dynamic"]["const"], json!(true));
+        assert_eq!(rule["then"]["required"], json!(["direction", "angle_initial", "angle_final"]));
+        assert_eq!(rule["then"]["not"], json!({ "required": ["angle"] }));
+        assert_eq!(rule["else"]["required"], json!(["angle"]));
+        let forbidden = rule["else"]["not"]["anyOf"].as_array().unwrap();
+        assert!(forbidden.contains(&json!({ "required": ["direction"] })));
+        assert!(forbidden.contains(&json!({ "required": ["resistance_curve"] })));
+    }
+
+    #[test]
+    fn joint_directions_come_from_the_given_taxonomy() {
+        let mut taxonomy = Taxonomy::builtin();
+        taxonomy.extend(Taxonomy {
+            joints: vec![
+                JointSpec { name: "Toe".to_string(), actions: vec![JointAction::Flexion, JointAction::Extension] },
+                JointSpec { name: "Jaw".to_string(), actions: vec![] },
+            ],
+            ..Taxonomy::default()
+        });
+        let schema = exercise_schema(&taxonomy);
+        let rules = joint_rules(&schema);
+        let rule_for = |name: &str| rules.iter().find(|r| r["if"]["properties"]["name"]["const"] == json!(name));
+
+        let toe = rule_for("Toe").expect("a rule for the user-defined joint");
+        assert_eq!(
+            toe["then"]["properties"]["direction"]["enum"],
+            json!([JointAction::Flexion, JointAction::Extension])
+        );
+        // A joint without listed actions allows any action, so it gets no rule
+        assert!(rule_for("Jaw").is_none());
+
+        let builtin = exercise_schema(&Taxonomy::builtin());
+        assert!(joint_rules(&builtin).iter().all(|r| r["if"]["properties"]["name"]["const"] != json!("Toe")));
+    }
+}
@@ -0,0 +1,322 @@
+//! How alike two exercises are, for finding a substitute when the usual
+//! exercise cannot be done.
+//!
+//! Each part of the comparison scores from 0 to 1 and the overall score is
+//! their weighted sum, so it is 1 for two exercises that differ only in name
+//! and description.
+
+use crate::library::{Library, LibraryEntry};
+use crate::models::*;
+use std::path::Path;
+
+/// Share of the overall score given to each part of the comparison.
+const MUSCLE_WEIGHT: f64 = 0.35;
+const JOINT_WEIGHT: f64 = 0.25;
+const RANGE_WEIGHT: f64 = 0.15;
+const PROFILE_WEIGHT: f64 = 0.15;
+const PLANE_WEIGHT: f64 = 0.10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Similarity {
+    pub score: f64,
+    /// Overlap of the targeted muscles and divisions, weighted by role and emphasis.
+    pub muscles: f64,
+    /// Share of the dynamic joints the two move in the same direction. A
+    /// joint both move, but in different directions, counts a quarter.
+    pub joints: f64,
+    /// Overlap of the ranges of motion of the joints they share.
+    pub range: f64,
+    /// 1 if the profiles match, either directly or through an equipment variant.
+    pub profile: f64,
+    pub plane: f64,
+}
+
+/// A library exercise ranked against the one being replaced.
+#[derive(Debug)]
+pub struct Substitute<'a> {
+    pub entry: &'a LibraryEntry,
+    pub similarity: Similarity,
+}
+
+impl Exercise {
+    pub fn similarity(&self, other: &Exercise) -> Similarity {
+        let muscles = muscle_overlap(self, other);
+        let (joints, range) = joint_overlap(self, other);
+        let profiles = |e: &Exercise| {
+            let mut profiles = vec![e.resistance_profile.clone()];
+            profiles.extend(e.variants.iter().map(|v| v.resistance_profile.clone()));
+            profiles
+        };
+        let other_profiles = profiles(other);
+        let profile = if profiles(self).iter().any(|p| other_profiles.contains(p)) { 1.0 } else { 0.0 };
+        let plane = if self.plane_of_motion == other.plane_of_motion { 1.0 } else { 0.0 };
+        Similarity {
+            score: MUSCLE_WEIGHT * muscles
+                + JOINT_WEIGHT * joints
+                + RANGE_WEIGHT * range
+                + PROFILE_WEIGHT * profile
+                + PLANE_WEIGHT * plane,
+            muscles,
+            joints,
+            range,
+            profile,
+            plane,
+        }
+    }
+}
+
+impl Library {
+    /// Every exercise in the library ranked by similarity to `exercise`,
+    /// most similar first, leaving out the file at `exclude` however its
+    /// path is spelled.
+    pub fn substitutes(&self, exercise: &Exercise, exclude: Option<&Path>) -> Vec<Substitute<'_>> {
+        let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let exclude = exclude.map(canonical);
+        let mut substitutes: Vec<Substitute> = self
+            .entries
+            .iter()
+            .filter(|entry| exclude.as_ref().is_none_or(|exclude| canonical(&entry.path) != *exclude))
+            .map(|entry| Substitute {
+                entry,
+                similarity: exercise.similarity(&entry.exercise),
+            })
+            .collect();
+        substitutes.sort_by(|a, b| b.similarity.score.total_cmp(&a.similarity.score));
+        substitutes
+    }
+}
+
+/// How much a division counts: its emphasis scaled by the muscle's role.
+fn division_weights(exercise: &Exercise) -> Vec<(String, String, f64)> {
+    let mut weights = Vec::new();
+    for muscle in &exercise.target_muscles.muscles {
+        let role = match muscle.role {
+            MuscleRole::Primary => 1.0,
+            MuscleRole::Secondary => 0.5,
+            MuscleRole::Stabilizer => 0.25,
+        };
+        for division in muscle.active_divisions() {
+            let emphasis = match division.emphasis {
+                Emphasis::None => 0.0,
+                Emphasis::Low => 1.0,
+                Emphasis::Moderate => 2.0,
+                Emphasis::High => 3.0,
+            };
+            weights.push((muscle.muscle_name.clone(), division.name.clone(), role * emphasis));
+        }
+    }
+    weights
+}
+
+/// Weighted Jaccard overlap of the divisions the two exercises target.
+fn muscle_overlap(a: &Exercise, b: &Exercise) -> f64 {
+    let a = division_weights(a);
+    let b = division_weights(b);
+    let weight_in = |weights: &[(String, String, f64)], muscle: &str, division: &str| {
+        weights
+            .iter()
+            .filter(|(m, d, _)| m == muscle && d == division)
+            .map(|(_, _, w)| *w)
+            .sum::<f64>()
+    };
+
+    let mut shared = 0.0;
+    let mut total = 0.0;
+    for (muscle, division, weight) in &a {
+        let other = weight_in(&b, muscle, division);
+        shared += weight.min(other);
+        total += weight.max(other);
+    }
+    for (muscle, division, weight) in &b {
+        if weight_in(&a, muscle, division) == 0.0 {
+            total += weight;
+        }
+    }
+    if total > 0.0 { shared / total } else { 0.0 }
+}
+
+/// Share of dynamic joints moved the same way by both, and the average
+/// overlap of their ranges of motion.
+fn joint_overlap(a: &Exercise, b: &Exercise) -> (f64, f64) {
+    let a: Vec<&Joint> = a.joints_involved.joints.iter().filter(|j| j.dynamic).collect();
+    let b: Vec<&Joint> = b.joints_involved.joints.iter().filter(|j| j.dynamic).collect();
+    let count = a.len().max(b.len());
+    if count == 0 {
+        return (0.0, 0.0);
+    }
+
+    let mut joints = 0.0;
+    let mut ranges = Vec::new();
+    for joint in &a {
+        let Some(other) = b.iter().find(|o| o.name == joint.name) else {
+            continue;
+        };
+        if joint.direction.is_some() && joint.direction == other.direction {
+            joints += 1.0;
+            ranges.push(range_overlap(joint, other));
+        } else {
+            // Same joint moving another way still has something in common
+            joints += 0.25;
+        }
+    }
+    let range = if ranges.is_empty() { 0.0 } else { ranges.iter().sum::<f64>() / ranges.len() as f64 };
+    (joints / count as f64, range)
+}
+
+/// Length of the shared part of two ranges of motion over their combined length.
+fn range_overlap(a: &Joint, b: &Joint) -> f64 {
+    let range = |j: &Joint| {
+        let (from, to) = (j.angle_initial? as f64, j.angle_final? as f64);
+        Some((from.min(to), from.max(to)))
+    };
+    let (Some((a_low, a_high)), Some((b_low, b_high))) = (range(a), range(b)) else {
+        return 0.0;
+    };
+    let union = a_high.max(b_high) - a_low.min(b_low);
+    if union == 0.0 {
+        return 1.0;
+    }
+    (a_high.min(b_high) - a_low.max(b_low)).max(0.0) / union
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn muscle(name: &str, role: MuscleRole, divisions: &[(&str, Emphasis)]) -> TargetMuscle {
+        TargetMuscle {
+            muscle_divisions: divisions
+                .iter()
+                .map(|(name, emphasis)| MuscleDivision { name: name.to_string(), emphasis: *emphasis })
+                .collect(),
+            ..TargetMuscle::new(name.to_string(), role)
+        }
+    }
+
+    fn moving(name: &str, action: JointAction, from: u32, to: u32) -> Joint {
+        Joint {
+            name: name.to_string(),
+            dynamic: true,
+            angle: None,
+            direction: Some(action),
+            unmapped_direction: None,
+            angle_initial: Some(from),
+            angle_final: Some(to),
+            resistance_curve: None,
+        }
+    }
+
+    fn curl() -> Exercise {
+        Exercise {
+            name: "curl".to_string(),
+            target_muscles: TargetMuscles {
+                muscles: vec![muscle("biceps", MuscleRole::Primary, &[("long head", Emphasis::High)])],
+            },
+            joints_involved: JointsInvolved { joints: vec![moving("elbow", JointAction::Flexion, 180, 40)] },
+            resistance_profile: ResistanceProfile::Bell,
+            plane_of_motion: PlaneOfMotion::Sagittal,
+            ..Exercise::default()
+        }
+    }
+
+    #[test]
+    fn identical_exercises_score_one() {
+        let similarity = curl().similarity(&Exercise { description: "different words".to_string(), ..curl() });
+        assert_eq!(
+            similarity,
+            Similarity { score: 1.0, muscles: 1.0, joints: 1.0, range: 1.0, profile: 1.0, plane: 1.0 }
+        );
+    }
+
+    #[test]
+    fn unrelated_exercises_score_low() {
+        let raise = Exercise {
+            target_muscles: TargetMuscles {
+                muscles: vec![muscle("shoulders", MuscleRole::Primary, &[("lateral deltoid", Emphasis::High)])],
+            },
+            joints_involved: JointsInvolved { joints: vec![moving("shoulder", JointAction::Abduction, 0, 90)] },
+            resistance_profile: ResistanceProfile::Ascending,
+            plane_of_motion: PlaneOfMotion::Frontal,
+            ..Exercise::default()
+        };
+        let similarity = curl().similarity(&raise);
+        assert_eq!(similarity.muscles, 0.0);
+        assert_eq!(similarity.score, 0.0);
+        // Symmetric
+        assert_eq!(raise.similarity(&curl()), similarity);
+    }
+
+    #[test]
+    fn score_is_the_weighted_sum_of_its_parts() {
+        let hammer_curl = Exercise {
+            target_muscles: TargetMuscles {
+                muscles: vec![
+                    muscle("biceps", MuscleRole::Primary, &[("long head", Emphasis::Moderate)]),
+                    muscle("brachialis", MuscleRole::Secondary, &[("brachialis", Emphasis::Moderate)]),
+                ],
+            },
+            joints_involved: JointsInvolved {
+                joints: vec![
+                    moving("elbow", JointAction::Flexion, 150, 30),
+                    moving("wrist", JointAction::RadialDeviation, 90, 60),
+                ],
+            },
+            resistance_profile: ResistanceProfile::Ascending,
+            variants: vec![EquipmentVariant {
+                equipment: Equipment::Cable,
+                resistance_profile: ResistanceProfile::Bell,
+                ..EquipmentVariant::default()
+            }],
+            plane_of_motion: PlaneOfMotion::Frontal,
+            ..Exercise::default()
+        };
+        let s = curl().similarity(&hammer_curl);
+
+        // Long head 3 vs 2, brachialis only in one: 2 shared of 3 + 1
+        assert!((s.muscles - 0.5).abs() < 1e-9);
+        // Elbow flexion shared out of two moving joints
+        assert!((s.joints - 0.5).abs() < 1e-9);
+        // 40-150 shared of 30-180
+        assert!((s.range - 110.0 / 150.0).abs() < 1e-9);
+        // The bell profile comes through the cable variant
+        assert_eq!((s.profile, s.plane), (1.0, 0.0));
+        let expected = MUSCLE_WEIGHT * 0.5 + JOINT_WEIGHT * 0.5 + RANGE_WEIGHT * 110.0 / 150.0 + PROFILE_WEIGHT;
+        assert!((s.score - expected).abs() < 1e-9);
+        let weights = MUSCLE_WEIGHT + JOINT_WEIGHT + RANGE_WEIGHT + PROFILE_WEIGHT + PLANE_WEIGHT;
+        assert!((weights - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn same_joint_moving_another_way_counts_a_quarter() {
+        let reverse = Exercise {
+            joints_involved: JointsInvolved { joints: vec![moving("elbow", JointAction::Extension, 40, 180)] },
+            ..curl()
+        };
+        let s = curl().similarity(&reverse);
+        assert_eq!((s.joints, s.range), (0.25, 0.0));
+    }
+
+    #[test]
+    fn substitutes_leave_out_the_excluded_file_however_it_is_spelled() {
+        let dir = std::env::temp_dir().join(format!("hypertrophy_archive_similar_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let entries: Vec<LibraryEntry> = ["curl.json", "other_curl.json"]
+            .into_iter()
+            .map(|name| {
+                let path = dir.join(name);
+                fs::write(&path, "").unwrap();
+                LibraryEntry { path, exercise: curl() }
+            })
+            .collect();
+        let library = Library { entries, ..Library::default() };
+
+        let spelled: PathBuf = dir.join(".").join("curl.json");
+        let substitutes = library.substitutes(&curl(), Some(&spelled));
+        let paths: Vec<&Path> = substitutes.iter().map(|s| s.entry.path.as_path()).collect();
+        assert_eq!(paths, [dir.join("other_curl.json")]);
+        assert_eq!(library.substitutes(&curl(), None).len(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
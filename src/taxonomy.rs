@@ -0,0 +1,163 @@
+//! The muscles, muscle divisions and joints exercises can refer to, and how
+//! joint angles change the length of each division.
+//!
+//! A default taxonomy is compiled in from `assets/taxonomy.json`. Users can
+//! add muscles, divisions and joints without recompiling by placing their own
+//! file at `<data_dir>/config/taxonomy.json`; it is merged on top of the
+//! default.
+
+use crate::library::StorageError;
+use crate::models::JointAction;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const BUILTIN: &str = include_str!("../assets/taxonomy.json");
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Taxonomy {
+    #[serde(default)]
+    pub muscles: Vec<MuscleGroup>,
+    #[serde(default)]
+    pub joints: Vec<JointSpec>,
+    #[serde(default)]
+    pub muscle_lengths: Vec<LengthModel>,
+}
+
+/// A target muscle and the divisions that can be marked active for it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MuscleGroup {
+    pub name: String,
+    #[serde(default)]
+    pub divisions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JointSpec {
+    pub name: String,
+    /// Actions a dynamic joint of this kind may perform. An empty list allows any action.
+    #[serde(default)]
+    pub actions: Vec<JointAction>,
+}
+
+impl JointSpec {
+    /// The actions to offer for this joint.
+    pub fn allowed_actions(&self) -> &[JointAction] {
+        if self.actions.is_empty() { &JointAction::ALL } else { &self.actions }
+    }
+
+    pub fn allows(&self, action: JointAction) -> bool {
+        self.allowed_actions().contains(&action)
+    }
+}
+
+/// Which joints a muscle division crosses and at which angles of each it is
+/// longest and shortest. Biarticular muscles list both joints.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LengthModel {
+    pub muscle: String,
+    pub division: String,
+    pub joints: Vec<JointLength>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JointLength {
+    pub joint: String,
+    pub longest_at: u32,
+    pub shortest_at: u32,
+    /// How much this joint contributes to the division's length relative to
+    /// the other joints it crosses.
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+impl Taxonomy {
+    /// The taxonomy shipped with the app.
+    pub fn builtin() -> Taxonomy {
+        serde_json::from_str(BUILTIN).expect("assets/taxonomy.json is valid")
+    }
+
+    /// Reads a taxonomy file on its own, without merging it into the default.
+    pub fn load(path: &Path) -> Result<Taxonomy, StorageError> {
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Where the user taxonomy for a data directory lives.
+    pub fn user_path(data_dir: &Path) -> PathBuf {
+        data_dir.join("config").join("taxonomy.json")
+    }
+
+    /// The default taxonomy extended with the user file for `data_dir`, if there is one.
+    pub fn for_data_dir(data_dir: &Path) -> Result<Taxonomy, StorageError> {
+        let mut taxonomy = Taxonomy::builtin();
+        let user_path = Taxonomy::user_path(data_dir);
+        if user_path.exists() {
+            taxonomy.extend(Taxonomy::load(&user_path)?);
+        }
+        Ok(taxonomy)
+    }
+
+    /// Adds the muscles, divisions, joints and joint actions from `other` that
+    /// are not already present. Length models in `other` replace the ones for
+    /// the same division.
+    pub fn extend(&mut self, other: Taxonomy) {
+        for group in other.muscles {
+            match self.muscles.iter_mut().find(|m| m.name == group.name) {
+                Some(existing) => {
+                    for division in group.divisions {
+                        if !existing.divisions.contains(&division) {
+                            existing.divisions.push(division);
+                        }
+                    }
+                }
+                None => self.muscles.push(group),
+            }
+        }
+        for joint in other.joints {
+            match self.joints.iter_mut().find(|j| j.name == joint.name) {
+                Some(existing) => {
+                    for action in joint.actions {
+                        if !existing.actions.contains(&action) {
+                            existing.actions.push(action);
+                        }
+                    }
+                }
+                None => self.joints.push(joint),
+            }
+        }
+        for model in other.muscle_lengths {
+            match self
+                .muscle_lengths
+                .iter_mut()
+                .find(|m| m.muscle == model.muscle && m.division == model.division)
+            {
+                Some(existing) => *existing = model,
+                None => self.muscle_lengths.push(model),
+            }
+        }
+    }
+
+    pub fn muscle(&self, name: &str) -> Option<&MuscleGroup> {
+        self.muscles.iter().find(|m| m.name == name)
+    }
+
+    /// Divisions of `muscle`, or an empty slice for unknown muscles.
+    pub fn divisions(&self, muscle: &str) -> &[String] {
+        self.muscle(muscle).map_or(&[], |m| m.divisions.as_slice())
+    }
+
+    pub fn joint(&self, name: &str) -> Option<&JointSpec> {
+        self.joints.iter().find(|j| j.name == name)
+    }
+
+    pub fn length_model(&self, muscle: &str, division: &str) -> Option<&LengthModel> {
+        self.muscle_lengths
+            .iter()
+            .find(|m| m.muscle == muscle && m.division == division)
+    }
+}
@@ -0,0 +1,165 @@
+//! A local training log: what was actually done, set by set, against the
+//! archive's exercises.
+//!
+//! Each session is one JSON file in `<data-dir>/log`, named after its date.
+//! Loads are plain numbers in whatever unit the lifter uses.
+
+use crate::library::{json_files, write_atomic, Library, StorageError};
+use crate::program::Session;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LoggedSession {
+    pub date: NaiveDate,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+    #[serde(default)]
+    pub exercises: Vec<LoggedExercise>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LoggedExercise {
+    pub exercise_id: u32,
+    #[serde(default)]
+    pub sets: Vec<LoggedSet>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct LoggedSet {
+    pub reps: u32,
+    pub load: f64,
+    /// Reps in reserve at the end of the set, if it was rated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rir: Option<u32>,
+}
+
+impl LoggedSession {
+    pub fn new(date: NaiveDate) -> Self {
+        Self {
+            date,
+            name: String::new(),
+            notes: String::new(),
+            exercises: Vec::new(),
+        }
+    }
+
+    /// A session to fill in from a program session: one exercise per slot,
+    /// with its sets at the bottom of the rep range and the target RIR.
+    pub fn from_program(date: NaiveDate, session: &Session) -> Self {
+        Self {
+            name: session.name.clone(),
+            exercises: session
+                .slots
+                .iter()
+                .map(|slot| LoggedExercise {
+                    exercise_id: slot.exercise_id,
+                    sets: vec![
+                        LoggedSet {
+                            reps: slot.reps.min,
+                            load: 0.0,
+                            rir: Some(slot.rir),
+                        };
+                        slot.sets as usize
+                    ],
+                    notes: String::new(),
+                })
+                .collect(),
+            ..Self::new(date)
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<LoggedSession, StorageError> {
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), StorageError> {
+        let json = serde_json::to_string_pretty(self)?;
+        write_atomic(path, json.as_bytes())?;
+        Ok(())
+    }
+
+    /// Exercise ids logged in the session that the library does not contain.
+    pub fn unknown_exercise_ids(&self, library: &Library) -> Vec<u32> {
+        let mut ids: Vec<u32> = self
+            .exercises
+            .iter()
+            .map(|e| e.exercise_id)
+            .filter(|&id| library.find_by_id(id).is_none())
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+}
+
+/// A session read from the log, with the file it came from.
+#[derive(Debug)]
+pub struct LogEntry {
+    pub path: PathBuf,
+    pub session: LoggedSession,
+}
+
+/// A `*.json` file in the log folder that could not be read as a session.
+#[derive(Debug)]
+pub struct LogFailure {
+    pub path: PathBuf,
+    pub error: StorageError,
+}
+
+/// Every session in a log folder, oldest first.
+#[derive(Debug, Default)]
+pub struct TrainingLog {
+    pub entries: Vec<LogEntry>,
+    pub failures: Vec<LogFailure>,
+}
+
+impl TrainingLog {
+    /// Where the log for a data directory lives.
+    pub fn dir_for_data_dir(data_dir: &Path) -> PathBuf {
+        data_dir.join("log")
+    }
+
+    /// Reads every `*.json` session in `dir`. A missing folder is an empty log.
+    pub fn load(dir: &Path) -> io::Result<TrainingLog> {
+        let mut log = TrainingLog::default();
+        for path in json_files(dir)? {
+            match LoggedSession::load(&path) {
+                Ok(session) => log.entries.push(LogEntry { path, session }),
+                Err(error) => log.failures.push(LogFailure { path, error }),
+            }
+        }
+        log.entries
+            .sort_by(|a, b| a.session.date.cmp(&b.session.date).then_with(|| a.path.cmp(&b.path)));
+        Ok(log)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// A file in `dir` for a new session on `date` that does not exist yet:
+/// `2024-05-01.json`, then `2024-05-01_2.json` and so on.
+pub fn new_session_path(dir: &Path, date: NaiveDate) -> PathBuf {
+    let stem = date.format("%Y-%m-%d").to_string();
+    let mut path = dir.join(format!("{}.json", stem));
+    let mut n = 2;
+    while path.exists() {
+        path = dir.join(format!("{}_{}.json", stem, n));
+        n += 1;
+    }
+    path
+}
@@ -0,0 +1,478 @@
+use crate::models::*;
+use crate::taxonomy::Taxonomy;
+use std::fmt;
+
+/// Errors block saving; warnings are shown but do not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// Everything the validator knows how to complain about.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IssueKind {
+    EmptyName,
+    EmptyDescription,
+    NoPrimaryMuscle,
+    EmptyMuscleName,
+    DuplicateMuscle(String),
+    NoActiveDivision,
+    NoJoints,
+    NoDynamicJoint,
+    DuplicateJoint(String),
+    MissingDirection,
+    /// A direction from an older file that matched no joint action.
+    UnmappedDirection(String),
+    MissingAngleRange,
+    NoRangeOfMotion,
+    MissingStaticAngle,
+    /// A field that only dynamic joints may have, named as in the file.
+    DynamicFieldOnStaticJoint(&'static str),
+    StaticAngleOnDynamicJoint,
+    AngleOutOfRange(u32),
+    TooManyDynamicJointsForIsolation(usize),
+    SingleDynamicJointForCompound,
+    InvalidVideoUrl,
+    UnknownMuscle(String),
+    UnknownDivision(String),
+    UnknownJoint(String),
+    IllegalJointAction { joint: String, action: JointAction },
+    CurveTooFewSamples,
+    NegativeTorque,
+    CurveSampleOutsideRange(u32),
+    ProfileDisagreesWithCurve(ResistanceProfile),
+    DuplicateVariant(String),
+}
+
+impl IssueKind {
+    pub fn severity(&self) -> Severity {
+        match self {
+            IssueKind::EmptyName
+            | IssueKind::NoPrimaryMuscle
+            | IssueKind::EmptyMuscleName
+            | IssueKind::MissingDirection
+            | IssueKind::UnmappedDirection(_)
+            | IssueKind::MissingAngleRange
+            | IssueKind::NoRangeOfMotion
+            | IssueKind::MissingStaticAngle
+            | IssueKind::DynamicFieldOnStaticJoint(_)
+            | IssueKind::StaticAngleOnDynamicJoint
+            | IssueKind::AngleOutOfRange(_)
+            | IssueKind::TooManyDynamicJointsForIsolation(_)
+            | IssueKind::IllegalJointAction { .. }
+            | IssueKind::CurveTooFewSamples
+            | IssueKind::NegativeTorque => Severity::Error,
+            IssueKind::EmptyDescription
+            | IssueKind::DuplicateMuscle(_)
+            | IssueKind::NoActiveDivision
+            | IssueKind::NoJoints
+            | IssueKind::NoDynamicJoint
+            | IssueKind::DuplicateJoint(_)
+            | IssueKind::SingleDynamicJointForCompound
+            | IssueKind::InvalidVideoUrl
+            | IssueKind::UnknownMuscle(_)
+            | IssueKind::UnknownDivision(_)
+            | IssueKind::UnknownJoint(_)
+            | IssueKind::CurveSampleOutsideRange(_)
+            | IssueKind::ProfileDisagreesWithCurve(_)
+            | IssueKind::DuplicateVariant(_) => Severity::Warning,
+        }
+    }
+}
+
+impl fmt::Display for IssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IssueKind::EmptyName => write!(f, "exercise name is empty"),
+            IssueKind::EmptyDescription => write!(f, "description is empty"),
+            IssueKind::NoPrimaryMuscle => write!(f, "no muscle has the primary role"),
+            IssueKind::EmptyMuscleName => write!(f, "no muscle selected"),
+            IssueKind::DuplicateMuscle(name) => {
+                write!(f, "muscle '{}' is listed more than once", name)
+            }
+            IssueKind::NoActiveDivision => write!(f, "no division of the primary muscle has any emphasis"),
+            IssueKind::NoJoints => write!(f, "no joints listed"),
+            IssueKind::NoDynamicJoint => write!(f, "no joint is dynamic"),
+            IssueKind::DuplicateJoint(name) => write!(f, "joint '{}' is listed more than once", name),
+            IssueKind::MissingDirection => write!(f, "dynamic joint has no direction"),
+            IssueKind::UnmappedDirection(text) => {
+                write!(f, "direction '{}' from an older file matches no joint action; pick one", text)
+            }
+            IssueKind::MissingAngleRange => {
+                write!(f, "dynamic joint needs both an initial and a final angle")
+            }
+            IssueKind::NoRangeOfMotion => write!(f, "initial and final angles are the same"),
+            IssueKind::MissingStaticAngle => write!(f, "static joint has no angle"),
+            IssueKind::DynamicFieldOnStaticJoint(field) => {
+                write!(f, "static joint cannot have {}, which is only for dynamic joints", field)
+            }
+            IssueKind::StaticAngleOnDynamicJoint => {
+                write!(f, "dynamic joint cannot have a fixed angle; use angle_initial and angle_final")
+            }
+            IssueKind::AngleOutOfRange(angle) => {
+                write!(f, "angle {}° is outside the 0-180° range", angle)
+            }
+            IssueKind::TooManyDynamicJointsForIsolation(count) => write!(
+                f,
+                "isolation exercise has {} dynamic joints (expected 1)",
+                count
+            ),
+            IssueKind::SingleDynamicJointForCompound => {
+                write!(f, "compound exercise has only one dynamic joint")
+            }
+            IssueKind::InvalidVideoUrl => write!(f, "technique video is not an http(s) URL"),
+            IssueKind::UnknownMuscle(name) => write!(f, "muscle '{}' is not in the taxonomy", name),
+            IssueKind::UnknownDivision(name) => {
+                write!(f, "division '{}' is not listed for this muscle in the taxonomy", name)
+            }
+            IssueKind::UnknownJoint(name) => write!(f, "joint '{}' is not in the taxonomy", name),
+            IssueKind::IllegalJointAction { joint, action } => {
+                write!(f, "the {} cannot perform {}", joint, action)
+            }
+            IssueKind::CurveTooFewSamples => write!(f, "resistance curve needs at least two samples"),
+            IssueKind::NegativeTorque => write!(f, "torque cannot be negative"),
+            IssueKind::CurveSampleOutsideRange(angle) => {
+                write!(f, "sample at {}° is outside the joint's range of motion", angle)
+            }
+            IssueKind::ProfileDisagreesWithCurve(derived) => write!(
+                f,
+                "resistance profile does not match the curve, which is {:?}",
+                derived
+            ),
+            IssueKind::DuplicateVariant(label) => write!(f, "variant '{}' is listed more than once", label),
+        }
+    }
+}
+
+/// A single problem, located by its JSON field path (e.g. `joints_involved.joint[1].direction`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationIssue {
+    pub path: String,
+    pub kind: IssueKind,
+}
+
+impl ValidationIssue {
+    pub fn severity(&self) -> Severity {
+        self.kind.severity()
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.kind)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidationReport {
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    fn push(&mut self, path: impl Into<String>, kind: IssueKind) {
+        self.issues.push(ValidationIssue {
+            path: path.into(),
+            kind,
+        });
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues.iter().filter(|i| i.severity() == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues.iter().filter(|i| i.severity() == Severity::Warning)
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.errors().next().is_some()
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+const MAX_ANGLE: u32 = 180;
+
+impl Exercise {
+    /// Checks the exercise for missing or contradictory data.
+    pub fn validate(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+
+        if self.name.trim().is_empty() {
+            report.push("name", IssueKind::EmptyName);
+        }
+        if self.description.trim().is_empty() {
+            report.push("description", IssueKind::EmptyDescription);
+        }
+        let video = self.technique_video.trim();
+        if !video.is_empty() && !video.starts_with("http://") && !video.starts_with("https://") {
+            report.push("technique_video", IssueKind::InvalidVideoUrl);
+        }
+
+        let muscles = &self.target_muscles.muscles;
+        if self.target_muscles.primary().is_none() {
+            report.push("target_muscles.muscle", IssueKind::NoPrimaryMuscle);
+        }
+        for (i, muscle) in muscles.iter().enumerate() {
+            let path = format!("target_muscles.muscle[{}]", i);
+            if muscle.muscle_name.trim().is_empty() {
+                report.push(format!("{}.muscle_name", path), IssueKind::EmptyMuscleName);
+                continue;
+            }
+            if muscles[..i].iter().any(|other| other.muscle_name == muscle.muscle_name) {
+                report.push(
+                    format!("{}.muscle_name", path),
+                    IssueKind::DuplicateMuscle(muscle.muscle_name.clone()),
+                );
+            }
+            if muscle.role == MuscleRole::Primary && muscle.active_divisions().next().is_none() {
+                report.push(format!("{}.muscle_division", path), IssueKind::NoActiveDivision);
+            }
+        }
+
+        let joints = &self.joints_involved.joints;
+        if joints.is_empty() {
+            report.push("joints_involved.joint", IssueKind::NoJoints);
+        }
+
+        for (i, joint) in joints.iter().enumerate() {
+            let path = format!("joints_involved.joint[{}]", i);
+
+            if joints[..i].iter().any(|other| other.name == joint.name) {
+                report.push(format!("{}.name", path), IssueKind::DuplicateJoint(joint.name.clone()));
+            }
+
+            // The same split the JSON Schema enforces with its if/then/else on `dynamic`
+            if joint.dynamic {
+                if joint.angle.is_some() {
+                    report.push(format!("{}.angle", path), IssueKind::StaticAngleOnDynamicJoint);
+                }
+                match (&joint.direction, &joint.unmapped_direction) {
+                    (Some(_), _) => {}
+                    (None, Some(text)) => {
+                        report.push(format!("{}.direction", path), IssueKind::UnmappedDirection(text.clone()))
+                    }
+                    (None, None) => report.push(format!("{}.direction", path), IssueKind::MissingDirection),
+                }
+                match (joint.angle_initial, joint.angle_final) {
+                    (Some(initial), Some(final_)) => {
+                        if initial == final_ {
+                            report.push(format!("{}.angle_final", path), IssueKind::NoRangeOfMotion);
+                        }
+                        check_angle(&mut report, format!("{}.angle_initial", path), initial);
+                        check_angle(&mut report, format!("{}.angle_final", path), final_);
+                    }
+                    _ => report.push(path.clone(), IssueKind::MissingAngleRange),
+                }
+                if let Some(curve) = &joint.resistance_curve {
+                    check_curve(&mut report, &path, joint, curve);
+                }
+            } else {
+                let dynamic_fields = [
+                    ("direction", joint.direction.is_some()),
+                    ("unmapped_direction", joint.unmapped_direction.is_some()),
+                    ("angle_initial", joint.angle_initial.is_some()),
+                    ("angle_final", joint.angle_final.is_some()),
+                    ("resistance_curve", joint.resistance_curve.is_some()),
+                ];
+                for (field, _) in dynamic_fields.into_iter().filter(|(_, set)| *set) {
+                    report.push(format!("{}.{}", path, field), IssueKind::DynamicFieldOnStaticJoint(field));
+                }
+                match joint.angle {
+                    Some(angle) => check_angle(&mut report, format!("{}.angle", path), angle),
+                    None => report.push(format!("{}.angle", path), IssueKind::MissingStaticAngle),
+                }
+            }
+        }
+
+        if let Some(derived) = self.derived_resistance_profile()
+            && derived != self.resistance_profile
+        {
+            report.push("resistance_profile", IssueKind::ProfileDisagreesWithCurve(derived));
+        }
+
+        for (i, variant) in self.variants.iter().enumerate() {
+            let duplicate = self.variants[..i].iter().any(|other| {
+                other.equipment == variant.equipment && other.name.trim().eq_ignore_ascii_case(variant.name.trim())
+            });
+            if duplicate {
+                report.push(format!("variants[{}]", i), IssueKind::DuplicateVariant(variant.label()));
+            }
+        }
+
+        let dynamic_joints = joints.iter().filter(|j| j.dynamic).count();
+        if !joints.is_empty() && dynamic_joints == 0 {
+            report.push("joints_involved.joint", IssueKind::NoDynamicJoint);
+        }
+        match self.r#type {
+            ExerciseType::Isolation if dynamic_joints > 1 => report.push(
+                "type",
+                IssueKind::TooManyDynamicJointsForIsolation(dynamic_joints),
+            ),
+            ExerciseType::Compound if dynamic_joints == 1 => {
+                report.push("type", IssueKind::SingleDynamicJointForCompound)
+            }
+            _ => {}
+        }
+
+        report
+    }
+
+    /// Runs [`Exercise::validate`] and also warns about muscles, divisions and
+    /// joints that `taxonomy` does not know about, and rejects joint actions
+    /// the taxonomy does not allow for their joint.
+    pub fn validate_with(&self, taxonomy: &Taxonomy) -> ValidationReport {
+        let mut report = self.validate();
+
+        for (i, muscle) in self.target_muscles.muscles.iter().enumerate() {
+            if muscle.muscle_name.trim().is_empty() {
+                continue;
+            }
+            let path = format!("target_muscles.muscle[{}]", i);
+            match taxonomy.muscle(&muscle.muscle_name) {
+                Some(group) => {
+                    for (j, division) in muscle.muscle_divisions.iter().enumerate() {
+                        if !group.divisions.contains(&division.name) {
+                            report.push(
+                                format!("{}.muscle_division[{}].name", path, j),
+                                IssueKind::UnknownDivision(division.name.clone()),
+                            );
+                        }
+                    }
+                }
+                None => report.push(
+                    format!("{}.muscle_name", path),
+                    IssueKind::UnknownMuscle(muscle.muscle_name.clone()),
+                ),
+            }
+        }
+
+        for (i, joint) in self.joints_involved.joints.iter().enumerate() {
+            let path = format!("joints_involved.joint[{}]", i);
+            match taxonomy.joint(&joint.name) {
+                Some(spec) => {
+                    if let Some(action) = joint.direction
+                        && joint.dynamic
+                        && !spec.allows(action)
+                    {
+                        report.push(
+                            format!("{}.direction", path),
+                            IssueKind::IllegalJointAction {
+                                joint: joint.name.clone(),
+                                action,
+                            },
+                        );
+                    }
+                }
+                None => report.push(
+                    format!("{}.name", path),
+                    IssueKind::UnknownJoint(joint.name.clone()),
+                ),
+            }
+        }
+
+        report
+    }
+}
+
+fn check_angle(report: &mut ValidationReport, path: String, angle: u32) {
+    if angle > MAX_ANGLE {
+        report.push(path, IssueKind::AngleOutOfRange(angle));
+    }
+}
+
+fn check_curve(report: &mut ValidationReport, path: &str, joint: &Joint, curve: &ResistanceCurve) {
+    let path = format!("{}.resistance_curve", path);
+    if curve.samples.len() < 2 {
+        report.push(format!("{}.sample", path), IssueKind::CurveTooFewSamples);
+    }
+    let range = match (joint.angle_initial, joint.angle_final) {
+        (Some(a), Some(b)) => Some(a.min(b)..=a.max(b)),
+        _ => None,
+    };
+    for (i, sample) in curve.samples.iter().enumerate() {
+        let sample_path = format!("{}.sample[{}]", path, i);
+        if sample.torque < 0.0 {
+            report.push(format!("{}.torque", sample_path), IssueKind::NegativeTorque);
+        }
+        if range.as_ref().is_some_and(|r| !r.contains(&sample.angle)) {
+            report.push(
+                format!("{}.angle", sample_path),
+                IssueKind::CurveSampleOutsideRange(sample.angle),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn joint(name: &str, dynamic: bool) -> Joint {
+        Joint {
+            name: name.to_string(),
+            dynamic,
+            angle: (!dynamic).then_some(45),
+            direction: dynamic.then_some(JointAction::Flexion),
+            unmapped_direction: None,
+            angle_initial: dynamic.then_some(180),
+            angle_final: dynamic.then_some(40),
+            resistance_curve: None,
+        }
+    }
+
+    fn joint_issues(joints: Vec<Joint>) -> Vec<(String, IssueKind)> {
+        let exercise = Exercise {
+            joints_involved: JointsInvolved { joints },
+            ..Exercise::default()
+        };
+        exercise
+            .validate()
+            .issues
+            .into_iter()
+            .filter(|issue| issue.path.starts_with("joints_involved.joint["))
+            .map(|issue| (issue.path, issue.kind))
+            .collect()
+    }
+
+    #[test]
+    fn well_formed_joints_have_no_issues() {
+        assert_eq!(joint_issues(vec![joint("shoulder", false), joint("elbow", true)]), []);
+    }
+
+    #[test]
+    fn static_joint_with_dynamic_fields_is_an_error() {
+        let mut shoulder = joint("shoulder", false);
+        shoulder.direction = Some(JointAction::Extension);
+        shoulder.resistance_curve = Some(ResistanceCurve::default());
+
+        let issues = joint_issues(vec![shoulder, joint("elbow", true)]);
+        assert_eq!(
+            issues,
+            [
+                (
+                    "joints_involved.joint[0].direction".to_string(),
+                    IssueKind::DynamicFieldOnStaticJoint("direction")
+                ),
+                (
+                    "joints_involved.joint[0].resistance_curve".to_string(),
+                    IssueKind::DynamicFieldOnStaticJoint("resistance_curve")
+                ),
+            ]
+        );
+        assert!(issues.iter().all(|(_, kind)| kind.severity() == Severity::Error));
+    }
+
+    #[test]
+    fn dynamic_joint_with_fixed_angle_is_an_error() {
+        let mut elbow = joint("elbow", true);
+        elbow.angle = Some(90);
+        assert_eq!(
+            joint_issues(vec![elbow]),
+            [("joints_involved.joint[0].angle".to_string(), IssueKind::StaticAngleOnDynamicJoint)]
+        );
+    }
+}
@@ -0,0 +1,417 @@
+//! Weekly training volume of a program per muscle and division, compared
+//! with volume landmarks.
+//!
+//! Volume is counted in hard sets: sets stopped within `hard_set_max_rir`
+//! reps of failure. Each set credits the exercise's target muscles by role,
+//! with less for secondary muscles and less again in isolation exercises,
+//! and each division further by its emphasis. Landmarks are the usual
+//! minimum effective (MEV), maximum adaptive (MAV) and maximum recoverable
+//! (MRV) volumes in weekly sets.
+
+use crate::library::{Library, StorageError};
+use crate::models::*;
+use crate::program::{Program, Week};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const BUILTIN: &str = include_str!("../assets/volume.json");
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VolumeConfig {
+    /// Sets with more reps in reserve than this are not counted.
+    pub hard_set_max_rir: u32,
+    pub credit: CreditTable,
+    pub emphasis_credit: EmphasisCredit,
+    #[serde(default)]
+    pub landmarks: Vec<Landmarks>,
+}
+
+/// Share of a set credited to a muscle, by exercise type and muscle role.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct CreditTable {
+    pub compound: RoleCredit,
+    pub isolation: RoleCredit,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct RoleCredit {
+    pub primary: f64,
+    pub secondary: f64,
+    pub stabilizer: f64,
+}
+
+/// Share of a muscle's credit passed on to a division, by its emphasis.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct EmphasisCredit {
+    pub low: f64,
+    pub moderate: f64,
+    pub high: f64,
+}
+
+/// Weekly set landmarks for a muscle, or for one of its divisions.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Landmarks {
+    pub muscle: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub division: Option<String>,
+    pub mev: f64,
+    pub mav: f64,
+    pub mrv: f64,
+}
+
+/// The user's `config/volume.json`. Settings it leaves out keep their
+/// default, and its landmarks replace the default ones they match.
+#[derive(Debug, Default, Deserialize)]
+struct UserVolumeConfig {
+    hard_set_max_rir: Option<u32>,
+    credit: Option<CreditTable>,
+    emphasis_credit: Option<EmphasisCredit>,
+    #[serde(default)]
+    landmarks: Vec<Landmarks>,
+}
+
+impl VolumeConfig {
+    pub fn builtin() -> VolumeConfig {
+        serde_json::from_str(BUILTIN).expect("assets/volume.json is valid")
+    }
+
+    /// Where the user volume settings for a data directory live.
+    pub fn user_path(data_dir: &Path) -> PathBuf {
+        data_dir.join("config").join("volume.json")
+    }
+
+    /// The default settings with the user file for `data_dir` applied, if there is one.
+    pub fn for_data_dir(data_dir: &Path) -> Result<VolumeConfig, StorageError> {
+        let mut config = VolumeConfig::builtin();
+        let user_path = VolumeConfig::user_path(data_dir);
+        if user_path.exists() {
+            let contents = fs::read_to_string(&user_path)?;
+            let user: UserVolumeConfig = serde_json::from_str(&contents)?;
+            config.hard_set_max_rir = user.hard_set_max_rir.unwrap_or(config.hard_set_max_rir);
+            config.credit = user.credit.unwrap_or(config.credit);
+            config.emphasis_credit = user.emphasis_credit.unwrap_or(config.emphasis_credit);
+            for landmarks in user.landmarks {
+                match config
+                    .landmarks
+                    .iter_mut()
+                    .find(|l| l.muscle == landmarks.muscle && l.division == landmarks.division)
+                {
+                    Some(existing) => *existing = landmarks,
+                    None => config.landmarks.push(landmarks),
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn credit(&self, exercise_type: &ExerciseType, role: MuscleRole) -> f64 {
+        let credit = match exercise_type {
+            ExerciseType::Compound => self.credit.compound,
+            ExerciseType::Isolation => self.credit.isolation,
+        };
+        match role {
+            MuscleRole::Primary => credit.primary,
+            MuscleRole::Secondary => credit.secondary,
+            MuscleRole::Stabilizer => credit.stabilizer,
+        }
+    }
+
+    pub fn emphasis_credit(&self, emphasis: Emphasis) -> f64 {
+        match emphasis {
+            Emphasis::None => 0.0,
+            Emphasis::Low => self.emphasis_credit.low,
+            Emphasis::Moderate => self.emphasis_credit.moderate,
+            Emphasis::High => self.emphasis_credit.high,
+        }
+    }
+
+    /// Landmarks for a whole muscle (`division` of `None`) or one division.
+    pub fn landmarks(&self, muscle: &str, division: Option<&str>) -> Option<&Landmarks> {
+        self.landmarks
+            .iter()
+            .find(|l| l.muscle == muscle && l.division.as_deref() == division)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VolumeStatus {
+    BelowMev,
+    MevToMav,
+    MavToMrv,
+    AboveMrv,
+}
+
+impl VolumeStatus {
+    pub fn label(self) -> &'static str {
+        match self {
+            VolumeStatus::BelowMev => "under MEV",
+            VolumeStatus::MevToMav => "MEV-MAV",
+            VolumeStatus::MavToMrv => "MAV-MRV",
+            VolumeStatus::AboveMrv => "over MRV",
+        }
+    }
+}
+
+impl Landmarks {
+    pub fn status(&self, sets: f64) -> VolumeStatus {
+        if sets < self.mev {
+            VolumeStatus::BelowMev
+        } else if sets < self.mav {
+            VolumeStatus::MevToMav
+        } else if sets <= self.mrv {
+            VolumeStatus::MavToMrv
+        } else {
+            VolumeStatus::AboveMrv
+        }
+    }
+}
+
+/// Weekly sets credited to a muscle or division. `status` is left out for
+/// deload weeks, which are meant to fall short of the landmarks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Volume {
+    pub name: String,
+    pub sets: f64,
+    pub landmarks: Option<Landmarks>,
+    pub status: Option<VolumeStatus>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MuscleVolume {
+    pub muscle: Volume,
+    pub divisions: Vec<Volume>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeekVolume {
+    /// Week number, starting at 1.
+    pub week: usize,
+    pub deload: bool,
+    pub muscles: Vec<MuscleVolume>,
+}
+
+impl WeekVolume {
+    /// Names of the muscles and divisions with `status`, divisions written
+    /// as "muscle division".
+    pub fn with_status(&self, status: VolumeStatus) -> Vec<String> {
+        let mut names = Vec::new();
+        for muscle in &self.muscles {
+            if muscle.muscle.status == Some(status) {
+                names.push(muscle.muscle.name.clone());
+            }
+            for division in &muscle.divisions {
+                if division.status == Some(status) {
+                    names.push(format!("{} {}", muscle.muscle.name, division.name));
+                }
+            }
+        }
+        names
+    }
+}
+
+impl Program {
+    /// Volume of every week of the program. Slots whose exercise is not in
+    /// `library` are skipped.
+    pub fn weekly_volume(&self, library: &Library, config: &VolumeConfig) -> Vec<WeekVolume> {
+        self.weeks
+            .iter()
+            .enumerate()
+            .map(|(i, week)| week_volume(i + 1, week, library, config))
+            .collect()
+    }
+}
+
+fn week_volume(number: usize, week: &Week, library: &Library, config: &VolumeConfig) -> WeekVolume {
+    let mut muscles: BTreeMap<String, (f64, BTreeMap<String, f64>)> = BTreeMap::new();
+    for slot in week.sessions.iter().flat_map(|s| &s.slots) {
+        if slot.rir > config.hard_set_max_rir {
+            continue;
+        }
+        let Some(entry) = library.find_by_id(slot.exercise_id) else {
+            continue;
+        };
+        let exercise = &entry.exercise;
+        for muscle in &exercise.target_muscles.muscles {
+            let credit = slot.sets as f64 * config.credit(&exercise.r#type, muscle.role);
+            if credit <= 0.0 {
+                continue;
+            }
+            let (sets, divisions) = muscles.entry(muscle.muscle_name.clone()).or_default();
+            *sets += credit;
+            for division in muscle.active_divisions() {
+                *divisions.entry(division.name.clone()).or_default() += credit * config.emphasis_credit(division.emphasis);
+            }
+        }
+    }
+
+    // Muscles with landmarks count even when the week does not train them
+    for landmarks in &config.landmarks {
+        let (_, divisions) = muscles.entry(landmarks.muscle.clone()).or_default();
+        if let Some(division) = &landmarks.division {
+            divisions.entry(division.clone()).or_default();
+        }
+    }
+
+    let volume = |name: &str, sets: f64, landmarks: Option<&Landmarks>| Volume {
+        name: name.to_string(),
+        sets,
+        landmarks: landmarks.cloned(),
+        status: landmarks.filter(|_| !week.deload).map(|l| l.status(sets)),
+    };
+    WeekVolume {
+        week: number,
+        deload: week.deload,
+        muscles: muscles
+            .iter()
+            .map(|(muscle, (sets, divisions))| MuscleVolume {
+                muscle: volume(muscle, *sets, config.landmarks(muscle, None)),
+                divisions: divisions
+                    .iter()
+                    .map(|(division, sets)| volume(division, *sets, config.landmarks(muscle, Some(division))))
+                    .collect(),
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::LibraryEntry;
+    use crate::program::{Session, Slot};
+
+    fn muscle(name: &str, role: MuscleRole, divisions: &[(&str, Emphasis)]) -> TargetMuscle {
+        let mut muscle = TargetMuscle::new(name.to_string(), role);
+        muscle.muscle_divisions = divisions
+            .iter()
+            .map(|&(name, emphasis)| MuscleDivision { name: name.to_string(), emphasis })
+            .collect();
+        muscle
+    }
+
+    fn exercise(id: u32, r#type: ExerciseType, muscles: Vec<TargetMuscle>) -> LibraryEntry {
+        LibraryEntry {
+            path: PathBuf::from(format!("{}.json", id)),
+            exercise: Exercise {
+                id,
+                r#type,
+                target_muscles: TargetMuscles { muscles },
+                ..Exercise::default()
+            },
+        }
+    }
+
+    fn library() -> Library {
+        let press = exercise(
+            0,
+            ExerciseType::Compound,
+            vec![
+                muscle(
+                    "chest",
+                    MuscleRole::Primary,
+                    &[("sternocostal", Emphasis::High), ("clavicular", Emphasis::Moderate), ("abdominal", Emphasis::Low)],
+                ),
+                muscle("shoulders", MuscleRole::Secondary, &[("anterior deltoid", Emphasis::Moderate)]),
+                muscle("triceps", MuscleRole::Secondary, &[]),
+            ],
+        );
+        let fly = exercise(
+            1,
+            ExerciseType::Isolation,
+            vec![
+                muscle("chest", MuscleRole::Primary, &[("sternocostal", Emphasis::High)]),
+                muscle("shoulders", MuscleRole::Secondary, &[("anterior deltoid", Emphasis::Low)]),
+            ],
+        );
+        Library {
+            entries: vec![press, fly],
+            ..Library::default()
+        }
+    }
+
+    fn slot(exercise_id: u32, sets: u32, rir: u32) -> Slot {
+        Slot { sets, rir, ..Slot::new(exercise_id) }
+    }
+
+    fn program() -> Program {
+        let week = |deload| Week {
+            deload,
+            sessions: vec![Session {
+                name: "Push".to_string(),
+                // Too far from failure to count, and an exercise that is not in the library
+                slots: vec![slot(0, 4, 2), slot(1, 3, 1), slot(1, 2, 5), slot(9, 5, 0)],
+            }],
+        };
+        Program {
+            name: "Test".to_string(),
+            weeks: vec![week(false), week(true)],
+            ..Program::default()
+        }
+    }
+
+    fn find<'a>(week: &'a WeekVolume, name: &str) -> &'a MuscleVolume {
+        week.muscles.iter().find(|m| m.muscle.name == name).unwrap()
+    }
+
+    fn division<'a>(muscle: &'a MuscleVolume, name: &str) -> &'a Volume {
+        muscle.divisions.iter().find(|d| d.name == name).unwrap()
+    }
+
+    #[test]
+    fn landmark_boundaries() {
+        let landmarks = Landmarks { muscle: "chest".to_string(), division: None, mev: 8.0, mav: 16.0, mrv: 22.0 };
+        assert_eq!(landmarks.status(7.99), VolumeStatus::BelowMev);
+        assert_eq!(landmarks.status(8.0), VolumeStatus::MevToMav);
+        assert_eq!(landmarks.status(15.99), VolumeStatus::MevToMav);
+        assert_eq!(landmarks.status(16.0), VolumeStatus::MavToMrv);
+        assert_eq!(landmarks.status(22.0), VolumeStatus::MavToMrv);
+        assert_eq!(landmarks.status(22.01), VolumeStatus::AboveMrv);
+    }
+
+    #[test]
+    fn sets_are_credited_by_role_and_exercise_type() {
+        let weeks = program().weekly_volume(&library(), &VolumeConfig::builtin());
+        let week = &weeks[0];
+
+        // 4 press + 3 fly sets; the 5 RIR sets and the unknown exercise do not count
+        let chest = find(week, "chest");
+        assert_eq!(chest.muscle.sets, 7.0);
+        assert_eq!(chest.muscle.status, Some(VolumeStatus::BelowMev));
+        // Half a set per compound set, a quarter per isolation set
+        assert_eq!(find(week, "shoulders").muscle.sets, 4.0 * 0.5 + 3.0 * 0.25);
+        assert_eq!(find(week, "triceps").muscle.sets, 2.0);
+    }
+
+    #[test]
+    fn divisions_get_a_share_by_emphasis() {
+        let weeks = program().weekly_volume(&library(), &VolumeConfig::builtin());
+        let chest = find(&weeks[0], "chest");
+        assert_eq!(division(chest, "sternocostal").sets, 7.0);
+        assert_eq!(division(chest, "clavicular").sets, 2.0);
+        assert_eq!(division(chest, "abdominal").sets, 1.0);
+
+        let shoulders = find(&weeks[0], "shoulders");
+        let anterior = division(shoulders, "anterior deltoid");
+        assert_eq!(anterior.sets, 4.0 * 0.5 * 0.5 + 3.0 * 0.25 * 0.25);
+        assert_eq!(anterior.status, Some(VolumeStatus::MevToMav));
+        // Divisions with landmarks are listed even when untrained
+        let lateral = division(shoulders, "lateral deltoid");
+        assert_eq!(lateral.sets, 0.0);
+        assert_eq!(lateral.status, Some(VolumeStatus::BelowMev));
+        let under = weeks[0].with_status(VolumeStatus::BelowMev);
+        assert!(under.contains(&"chest".to_string()));
+        assert!(under.contains(&"shoulders lateral deltoid".to_string()));
+    }
+
+    #[test]
+    fn deload_weeks_are_not_judged() {
+        let weeks = program().weekly_volume(&library(), &VolumeConfig::builtin());
+        let deload = &weeks[1];
+        assert!(deload.deload);
+        assert_eq!(find(deload, "chest").muscle.sets, 7.0);
+        assert!(deload.muscles.iter().all(|m| m.muscle.status.is_none()));
+        assert!(deload.with_status(VolumeStatus::BelowMev).is_empty());
+    }
+}